@@ -1,4 +1,17 @@
 import React from "react";
+import { chapters } from "@/constants/chapters";
+
+// Pre-render every chapter and page, so the site can be exported as static files
+export function generateStaticParams() {
+  const hrefs = chapters.flatMap((chapter) => [
+    chapter.href,
+    ...(chapter.pages ?? []).map((page) => page.href),
+  ]);
+
+  return hrefs
+    .filter((href) => href !== "")
+    .map((href) => ({ test: href.split("/").filter(Boolean) }));
+}
 
 export default function TestDoc() {
   return <div>TestDoc</div>;
@@ -1,3 +1,120 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
 fn main() {
     println!("cargo:rerun-if-changed=./migrations");
+
+    embed_guide();
+}
+
+/// Embed the exported docs site into the binary, so `krapi start` can serve it under `/guide`.
+///
+/// Release builds do it by default, which needs Node.js. Set `KRAPI_BUILD_GUIDE=0` to build without the site,
+/// or `KRAPI_BUILD_GUIDE=1` to include it in debug builds too. `next build` exports the site straight into `OUT_DIR`.
+///
+/// If the site should be built but can't be, the build fails.
+fn embed_guide() {
+    println!("cargo:rerun-if-env-changed=KRAPI_BUILD_GUIDE");
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let guide_dir = out_dir.join("guide");
+    if guide_dir.is_dir() {
+        fs::remove_dir_all(&guide_dir).unwrap();
+    }
+
+    let should_build = match env::var("KRAPI_BUILD_GUIDE").as_deref() {
+        Ok("1") => true,
+        Ok("0") => false,
+        _ => env::var("PROFILE").as_deref() == Ok("release"),
+    };
+
+    if should_build {
+        for path in [
+            "app",
+            "components",
+            "constants",
+            "styles",
+            "public",
+            "next.config.js",
+            "package.json",
+        ] {
+            println!("cargo:rerun-if-changed=./{}", path);
+        }
+
+        if let Err(e) = build_guide(&guide_dir) {
+            panic!(
+                "The docs site could not be built: {}\nInstall Node.js, or set KRAPI_BUILD_GUIDE=0 to build without it.",
+                e
+            );
+        }
+    }
+
+    // Generate a list of all the files with their contents
+    let mut files = Vec::new();
+    if guide_dir.is_dir() {
+        collect_files(&guide_dir, &mut files);
+    }
+    files.sort();
+
+    let mut code = String::from("pub static GUIDE_FILES: &[(&str, &[u8])] = &[\n");
+    for file in &files {
+        let name = file
+            .strip_prefix(&guide_dir)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .replace('\\', "/");
+
+        code.push_str(&format!("    ({:?}, include_bytes!({:?})),\n", name, file));
+    }
+    code.push_str("];\n");
+
+    fs::write(out_dir.join("guide.rs"), code).unwrap();
+}
+
+/// Export the site with `next build` into `dest`
+fn build_guide(dest: &Path) -> Result<(), String> {
+    let npm = if cfg!(windows) { "npm.cmd" } else { "npm" };
+
+    if !Path::new("node_modules").is_dir() {
+        run(Command::new(npm).arg("ci"))?;
+    }
+
+    // Read by `next.config.js`
+    run(Command::new(npm)
+        .args(["run", "build"])
+        .env("KRAPI_EMBED_GUIDE", "1")
+        .env("KRAPI_GUIDE_DIR", dest))?;
+
+    if dest.is_dir() {
+        Ok(())
+    } else {
+        Err(format!("next build didn't export the site into {:?}", dest))
+    }
+}
+
+fn run(command: &mut Command) -> Result<(), String> {
+    let status = command
+        .status()
+        .map_err(|e| format!("failed to run {:?}: {}", command, e))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("{:?} exited with {}", command, status))
+    }
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+
+        if path.is_dir() {
+            collect_files(&path, files);
+        } else {
+            files.push(path);
+        }
+    }
 }
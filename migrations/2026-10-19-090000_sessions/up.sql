@@ -0,0 +1,9 @@
+-- Your SQL goes here
+CREATE TABLE "sessions" (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    token TEXT NOT NULL UNIQUE,
+    csrf_token TEXT NOT NULL,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    expires_at DATETIME NOT NULL
+);
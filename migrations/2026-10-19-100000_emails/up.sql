@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE "emails" (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    recipient TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    body TEXT NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
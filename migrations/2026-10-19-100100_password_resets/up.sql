@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE "password_resets" (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    token TEXT NOT NULL UNIQUE,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    expires_at DATETIME NOT NULL
+);
@@ -0,0 +1,14 @@
+-- Your SQL goes here
+ALTER TABLE "users" ADD COLUMN email_verified_at DATETIME;
+
+-- The users from before there was a way to verify are taken as verified, or
+-- `--require-verified-email` would lock all of them out. See the README
+UPDATE "users" SET email_verified_at = created_at WHERE email IS NOT NULL;
+
+CREATE TABLE "email_verifications" (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    token TEXT NOT NULL UNIQUE,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    expires_at DATETIME NOT NULL
+);
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+CREATE TABLE "failed_logins" (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    user_id INTEGER REFERENCES users(id) ON DELETE CASCADE,
+    ip TEXT NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX "failed_logins_user_id" ON "failed_logins" (user_id);
+CREATE INDEX "failed_logins_ip" ON "failed_logins" (ip);
@@ -1,4 +1,19 @@
+// `build.rs` sets KRAPI_EMBED_GUIDE=1 to export a static copy of the site into KRAPI_GUIDE_DIR,
+// which it embeds into the krapi binary and serves under `/guide`.
+const path = require("path");
+
+const embedded = process.env.KRAPI_EMBED_GUIDE === "1";
+
 /** @type {import('next').NextConfig} */
-const nextConfig = {}
+const nextConfig = embedded
+  ? {
+      output: "export",
+      // Next.js joins it to the project directory, so it has to be relative
+      distDir: path.relative(__dirname, process.env.KRAPI_GUIDE_DIR ?? "out"),
+      basePath: "/guide",
+      trailingSlash: true,
+      images: { unoptimized: true },
+    }
+  : {};
 
 module.exports = nextConfig
@@ -1,26 +1,181 @@
-use crate::*;
+use crate::middleware::{
+    parse_chaos_rule, parse_chaos_timeout, parse_delay, parse_rate_limit, ChaosRule, DelayRange,
+    RateLimitKey, RateLimitRule,
+};
+use crate::routes::auth::CookieSameSite;
+use crate::utils::{parse_duration, ExportFormat, ImportFormat, Preset};
+use chrono::Duration;
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(author, version, about)]
 pub struct Cli {
+    /// Config file to use. Defaults to `krapi.toml` in the working directory, if there is one
+    #[clap(long, global = true, env = "KRAPI_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// The database file to use
+    #[clap(long, global = true, env = "KRAPI_DATABASE")]
+    pub database: Option<PathBuf>,
+
+    /// Memory in KiB used to hash a password. Lower it to make tests fast, raise it for a demo
+    /// of a real server [default: 19456]
+    #[clap(long, global = true, env = "KRAPI_HASH_MEMORY")]
+    pub hash_memory: Option<u32>,
+
+    /// How many passes hashing a password makes over the memory [default: 2]
+    #[clap(long, global = true, env = "KRAPI_HASH_ITERATIONS")]
+    pub hash_iterations: Option<u32>,
+
     #[clap(subcommand)]
     pub subcmd: SubCommand,
 }
 
+// Parsed once, so the size of `Start` doesn't matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 pub enum SubCommand {
     /// Start the server
     Start {
-        /// Port to listen on
-        #[clap(short, long, default_value = DEFAULT_PORT_STR)]
-        port: u16,
+        /// Host to listen on [default: localhost]
+        #[clap(long, env = "KRAPI_HOST")]
+        host: Option<String>,
+
+        /// Port to listen on [default: 8090]
+        #[clap(short, long, env = "KRAPI_PORT")]
+        port: Option<u16>,
+
+        /// Listen on this address instead of the host and port, like `0.0.0.0:8090` or `[::]`.
+        /// `--port` is used if the address has none. Can be given multiple times
+        #[clap(short, long, env = "KRAPI_BIND", value_delimiter = ',')]
+        bind: Vec<String>,
+
+        /// Listen on a Unix domain socket. The host and port are not used unless `--bind` is also given
+        #[clap(long, env = "KRAPI_UNIX_SOCKET")]
+        unix_socket: Option<PathBuf>,
+
+        /// Serve over HTTPS with a certificate for localhost, signed by a local CA that is generated once.
+        /// HTTP/2 is used with the clients that support it
+        #[clap(long, env = "KRAPI_TLS")]
+        tls: bool,
+
+        /// Serve over HTTPS with this certificate (PEM) instead. Needs `--tls-key`
+        #[clap(long, env = "KRAPI_TLS_CERT", requires = "tls_key")]
+        tls_cert: Option<PathBuf>,
+
+        /// The private key (PEM) of `--tls-cert`
+        #[clap(long, env = "KRAPI_TLS_KEY", requires = "tls_cert")]
+        tls_key: Option<PathBuf>,
+
+        /// Name of the session cookie [default: krapi_session]
+        #[clap(long, env = "KRAPI_SESSION_COOKIE")]
+        session_cookie: Option<String>,
+
+        /// The `SameSite` attribute of the session cookie [default: lax]
+        #[clap(long, env = "KRAPI_SESSION_SAME_SITE", value_enum)]
+        session_same_site: Option<CookieSameSite>,
+
+        /// Only send the session cookie over HTTPS [default: true with TLS]
+        #[clap(long, env = "KRAPI_SESSION_SECURE")]
+        session_secure: Option<bool>,
+
+        /// The `Domain` attribute of the session cookie
+        #[clap(long, env = "KRAPI_SESSION_DOMAIN")]
+        session_domain: Option<String>,
+
+        /// How long a session lasts, like `30m` or `7d` [default: 7d]
+        #[clap(long, env = "KRAPI_SESSION_TTL", value_parser = parse_duration)]
+        session_ttl: Option<Duration>,
+
+        /// Reject the logins of users that haven't verified their email [default: false]
+        #[clap(
+            long,
+            env = "KRAPI_REQUIRE_VERIFIED_EMAIL",
+            num_args = 0..=1,
+            default_missing_value = "true"
+        )]
+        require_verified_email: Option<bool>,
+
+        /// Lock an account after this many failed logins, `0` to never lock it [default: 5]
+        #[clap(long, env = "KRAPI_LOCKOUT_ATTEMPTS")]
+        lockout_attempts: Option<u32>,
+
+        /// Lock every login from an IP after this many failed logins from it, `0` to never lock it [default: 0]
+        #[clap(long, env = "KRAPI_LOCKOUT_IP_ATTEMPTS")]
+        lockout_ip_attempts: Option<u32>,
+
+        /// How long an account stays locked after the last failed login, like `15m` [default: 15m]
+        #[clap(long, env = "KRAPI_LOCKOUT_PERIOD", value_parser = parse_duration)]
+        lockout_period: Option<Duration>,
+
+        /// Enable the mock OpenID Connect provider: `/authorize`, `/token`, `/userinfo` and its discovery document.
+        /// Anyone who reaches the server can log in as any user through it [default: false]
+        #[clap(long, env = "KRAPI_OIDC", num_args = 0..=1, default_missing_value = "true")]
+        oidc: Option<bool>,
+
+        /// Enable the `/__admin` routes, guarded by this token
+        #[clap(long, env = "KRAPI_ADMIN_TOKEN")]
+        admin_token: Option<String>,
+
+        /// Hold every response for this long, like `300ms` or a random `200..800ms`
+        #[clap(short, long, env = "KRAPI_DELAY", value_parser = parse_delay)]
+        delay: Option<DelayRange>,
+
+        /// Make some requests fail, like `10%`, `25%:timeout` or `/get-user/*=50%:error,drop`.
+        /// The faults are error, timeout, drop and malformed. Can be given multiple times,
+        /// or separated by `;` in `KRAPI_CHAOS`
+        #[clap(long, env = "KRAPI_CHAOS", value_delimiter = ';', value_parser = parse_chaos_rule)]
+        chaos: Vec<ChaosRule>,
+
+        /// How long a request hangs before the timeout fault responds [default: 30s]
+        #[clap(long, env = "KRAPI_CHAOS_TIMEOUT", value_parser = parse_chaos_timeout)]
+        chaos_timeout: Option<std::time::Duration>,
+
+        /// Limit how many requests each client can make, like `60/m` or `100/15m`
+        #[clap(long, env = "KRAPI_RATE_LIMIT", value_parser = parse_rate_limit)]
+        rate_limit: Option<RateLimitRule>,
+
+        /// What identifies a client for the rate limit [default: ip]
+        #[clap(long, env = "KRAPI_RATE_LIMIT_KEY", value_enum)]
+        rate_limit_key: Option<RateLimitKey>,
+
+        /// Allow requests from this origin, like `http://localhost:3000`. Any origin is allowed if none is given.
+        /// Can be given multiple times
+        #[clap(long, env = "KRAPI_CORS_ORIGINS", value_delimiter = ',')]
+        cors_origin: Vec<String>,
+
+        /// Allow this method in cross-origin requests. Any method is allowed if none is given.
+        /// Can be given multiple times
+        #[clap(long, env = "KRAPI_CORS_METHODS", value_delimiter = ',')]
+        cors_method: Vec<String>,
+
+        /// Allow this request header in cross-origin requests. Any header is allowed if none is given.
+        /// Can be given multiple times
+        #[clap(long, env = "KRAPI_CORS_HEADERS", value_delimiter = ',')]
+        cors_header: Vec<String>,
+
+        /// Let the browser read this response header. Can be given multiple times
+        #[clap(long, env = "KRAPI_CORS_EXPOSE_HEADERS", value_delimiter = ',')]
+        cors_expose_header: Vec<String>,
+
+        /// Allow cookies and the `Authorization` header in cross-origin requests [default: true]
+        #[clap(long, env = "KRAPI_CORS_CREDENTIALS")]
+        cors_credentials: Option<bool>,
+
+        /// How many seconds the browser can cache a preflight response
+        #[clap(long, env = "KRAPI_CORS_MAX_AGE")]
+        cors_max_age: Option<usize>,
     },
     /// Generate random users
     Generate {
-        /// Number of data to generate
-        #[clap(short, long, default_value = DEFAULT_MOCK_DATA_LEN_STR)]
-        len: u32,
+        /// Number of data to generate [default: 10]
+        #[clap(short, long)]
+        len: Option<u32>,
+
+        /// Spread the creation dates over this long ago until now (e.g. `30d`, `6mo`, `2y`) [default: 1y]
+        #[clap(short, long, value_parser = parse_duration)]
+        since: Option<Duration>,
 
         /// Generate users
         #[clap(short, long)]
@@ -34,12 +189,81 @@ pub enum SubCommand {
         #[clap(short, long)]
         todos: bool,
     },
-    /// Show docs
+    /// Delete all the data from the database
+    Reset {
+        /// Drop all the tables and run the migrations again from scratch
+        #[clap(short, long)]
+        migrations: bool,
+
+        /// Load a preset after resetting
+        #[clap(short, long, value_enum)]
+        seed: Option<Preset>,
+    },
+    /// Load a preset into the database
+    Seed {
+        /// The preset to load [default: demo]
+        #[clap(value_enum)]
+        preset: Option<Preset>,
+    },
+    /// Import users from a JSON or CSV fixture file
+    Import {
+        /// The file to import
+        file: PathBuf,
+
+        /// Format of the file. Guessed from the extension if not given
+        #[clap(short, long, value_enum)]
+        format: Option<ImportFormat>,
+    },
+    /// Export the users, without their password hashes
+    Export {
+        /// Format of the exported files
+        #[clap(short, long, value_enum, default_value = "json")]
+        format: ExportFormat,
+
+        /// Directory to write the files into. Prints to stdout if not given
+        #[clap(short, long)]
+        out: Option<PathBuf>,
+    },
+    /// Save and restore copies of the database
+    Snapshot {
+        #[clap(subcommand)]
+        action: SnapshotAction,
+    },
+    /// Manage the API keys of machine clients
+    Keys {
+        #[clap(subcommand)]
+        action: KeysAction,
+    },
+    /// Print the OpenAPI 3 description of the server
+    Openapi {
+        /// Host the server is reached at [default: localhost]
+        #[clap(long, env = "KRAPI_HOST")]
+        host: Option<String>,
+
+        /// Port the server is reached at [default: 8090]
+        #[clap(short, long, env = "KRAPI_PORT")]
+        port: Option<u16>,
+    },
+    /// Generate TypeScript definitions for the API models
+    Typescript {
+        /// File to write the definitions into, like `krapi.d.ts`. Prints to stdout if not given
+        #[clap(short, long)]
+        out: Option<PathBuf>,
+    },
+    /// Show docs. Shows the docs for every resource if none is selected
     Docs {
         /// Show docs for users
         #[clap(short, long)]
         users: bool,
 
+        /// Show docs for the session routes
+        #[clap(long)]
+        auth: bool,
+
+        /// Show docs for the admin routes
+        #[clap(short, long)]
+        admin: bool,
+
         /// Show docs for posts
         #[clap(short, long)]
         posts: bool,
@@ -49,3 +273,43 @@ pub enum SubCommand {
         todos: bool,
     },
 }
+
+#[derive(Subcommand)]
+pub enum SnapshotAction {
+    /// Save a copy of the database
+    Save {
+        /// Name of the snapshot. Overwrites an existing snapshot with the same name
+        name: String,
+    },
+    /// Replace the database with a saved copy. The API keys are kept
+    Restore {
+        /// Name of the snapshot
+        name: String,
+    },
+    /// List the saved snapshots
+    List,
+}
+
+#[derive(Subcommand)]
+pub enum KeysAction {
+    /// Create an API key. The key is only shown this time
+    Create {
+        /// A name to tell the key apart, like `bff`
+        name: String,
+
+        /// What the key is allowed to do. Can be given more than once. `admin` opens the admin routes
+        #[clap(short, long = "scope")]
+        scopes: Vec<String>,
+
+        /// Expire the key after this long, like `30d`. Never expires if not given
+        #[clap(long, value_parser = parse_duration)]
+        expires_in: Option<Duration>,
+    },
+    /// List the API keys
+    List,
+    /// Delete an API key
+    Revoke {
+        /// Id of the key, from `krapi keys list`
+        id: i32,
+    },
+}
@@ -0,0 +1,203 @@
+use crate::middleware::{CorsOptions, RateLimitKey};
+use crate::routes::auth::CookieSameSite;
+use crate::utils::Preset;
+use crate::*;
+use std::path::{Path, PathBuf};
+
+/// The name of the config file that is looked up in the working directory
+pub const CONFIG_FILE: &str = "krapi.toml";
+
+/// The settings of a `krapi.toml` file
+///
+/// Every setting is optional. A setting given on the command line or in an environment variable
+/// wins over the file, and the file wins over the defaults.
+///
+/// ## Example
+///
+/// ```toml
+/// host = "localhost"
+/// port = 8090
+/// bind = ["0.0.0.0:8090"]
+/// database = "mock.sqlite"
+/// delay = "200..800ms"
+/// chaos = ["/get-user/*=10%:error"]
+/// rate-limit = "60/m"
+///
+/// [cors]
+/// origins = ["http://localhost:3000"]
+/// max-age = 600
+///
+/// [tls]
+/// enabled = true
+///
+/// [session]
+/// same-site = "strict"
+/// ttl = "1h"
+///
+/// [auth]
+/// admin-token = "secret"
+/// require-verified-email = true
+/// lockout-attempts = 3
+///
+/// [oidc]
+/// enabled = true
+///
+/// [hash]
+/// memory = 8
+/// iterations = 1
+///
+/// [seed]
+/// preset = "demo"
+/// len = 20
+/// since = "6mo"
+/// ```
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub bind: Vec<String>,
+    /// Relative to the config file
+    pub unix_socket: Option<PathBuf>,
+    /// Relative to the config file
+    pub database: Option<PathBuf>,
+    pub delay: Option<String>,
+    pub chaos: Vec<String>,
+    pub chaos_timeout: Option<String>,
+    pub rate_limit: Option<String>,
+    pub rate_limit_key: Option<RateLimitKey>,
+    pub cors: CorsOptions,
+    pub tls: TlsConfig,
+    pub session: SessionConfig,
+    pub auth: AuthConfig,
+    pub oidc: OidcConfig,
+    pub hash: HashConfig,
+    pub seed: SeedConfig,
+}
+
+/// The `[tls]` table
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct TlsConfig {
+    /// Serve over HTTPS with a certificate signed by the local CA
+    pub enabled: Option<bool>,
+    /// Relative to the config file
+    pub cert: Option<PathBuf>,
+    /// Relative to the config file
+    pub key: Option<PathBuf>,
+}
+
+/// The `[session]` table
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct SessionConfig {
+    /// Name of the session cookie
+    pub cookie: Option<String>,
+    pub same_site: Option<CookieSameSite>,
+    pub secure: Option<bool>,
+    pub domain: Option<String>,
+    /// How long a session lasts, like `7d`
+    pub ttl: Option<String>,
+}
+
+/// The `[auth]` table
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct AuthConfig {
+    pub admin_token: Option<String>,
+    /// Reject logins of users that haven't verified their email
+    pub require_verified_email: Option<bool>,
+    /// How many failed logins lock an account, `0` to never lock it
+    pub lockout_attempts: Option<u32>,
+    /// How many failed logins from an IP lock every login from it, `0` to never lock it
+    pub lockout_ip_attempts: Option<u32>,
+    /// How long an account stays locked, like `15m`
+    pub lockout_period: Option<String>,
+}
+
+/// The `[oidc]` table
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct OidcConfig {
+    /// Enable the mock OpenID Connect provider
+    pub enabled: Option<bool>,
+}
+
+/// The `[hash]` table
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct HashConfig {
+    /// Memory in KiB used to hash a password
+    pub memory: Option<u32>,
+    /// Passes over the memory
+    pub iterations: Option<u32>,
+}
+
+/// The `[seed]` table
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct SeedConfig {
+    /// The preset `krapi seed` loads
+    pub preset: Option<Preset>,
+    /// How many users `krapi generate` makes
+    pub len: Option<u32>,
+    /// How far back `krapi generate` spreads the creation dates
+    pub since: Option<String>,
+}
+
+impl Config {
+    /// Load the config file
+    ///
+    /// If `path` is `None`, `krapi.toml` is looked up in the working directory, and the defaults are used when there is none.
+    pub fn load(path: Option<&Path>) -> Result<Self, String> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => (PathBuf::from(CONFIG_FILE), false),
+        };
+
+        if !required && !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+        let mut config: Self = toml::from_str(&content)
+            .map_err(|e| format!("Invalid config in {}: {}", path.display(), e))?;
+
+        // The paths are relative to the config file, not to where the command is run
+        if let Some(dir) = path.parent() {
+            config.database = config.database.map(|database| dir.join(database));
+            config.unix_socket = config.unix_socket.map(|socket| dir.join(socket));
+            config.tls.cert = config.tls.cert.map(|cert| dir.join(cert));
+            config.tls.key = config.tls.key.map(|key| dir.join(key));
+        }
+
+        Ok(config)
+    }
+
+    /// Parse a setting of the file with the same parser as its command line flag
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use krapi::config::Config;
+    /// # use krapi::middleware::parse_delay;
+    /// #
+    /// let delay = Config::parse("delay", Some("300ms"), parse_delay).unwrap();
+    /// assert!(delay.is_some());
+    ///
+    /// assert!(Config::parse("delay", Some("soon"), parse_delay).is_err());
+    /// ```
+    pub fn parse<T>(
+        key: &str,
+        value: Option<&str>,
+        parser: fn(&str) -> Result<T, String>,
+    ) -> Result<Option<T>, String> {
+        value
+            .map(|value| {
+                parser(value).map_err(|e| format!("Invalid `{}` in the config: {}", key, e))
+            })
+            .transpose()
+    }
+}
@@ -3,15 +3,26 @@
 #[macro_use]
 extern crate diesel;
 
+pub const DEFAULT_HOST: &str = "localhost";
+
 pub const DEFAULT_PORT: usize = 8090;
+#[deprecated(note = "use `DEFAULT_PORT`")]
 pub const DEFAULT_PORT_STR: &str = "8090";
 
 pub const DATABASE_PATH: &str = "krapi.sqlite";
 
+/// Overrides [`DATABASE_PATH`] when set
+pub const DATABASE_ENV: &str = "KRAPI_DATABASE";
+
 pub const DEFAULT_MOCK_DATA_LEN: usize = 10;
+#[deprecated(note = "use `DEFAULT_MOCK_DATA_LEN`")]
 pub const DEFAULT_MOCK_DATA_LEN_STR: &str = "10";
 
+pub const DEFAULT_MOCK_DATA_SINCE_STR: &str = "1y";
+
 pub mod cli;
+pub mod config;
+pub mod middleware;
 pub mod models;
 pub mod routes;
 pub mod schema;
@@ -20,13 +31,23 @@ pub mod utils;
 pub(crate) use actix_web::{
     get, post,
     web::{self, Data, Json, Path},
-    HttpResponse,
+    HttpRequest, HttpResponse,
 };
 pub(crate) use diesel::prelude::*;
 pub(crate) use diesel::SqliteConnection;
+pub(crate) use models::api_keys::*;
+pub(crate) use models::email_verifications::*;
+pub(crate) use models::emails::*;
+pub(crate) use models::failed_logins::*;
+pub(crate) use models::oidc::*;
+pub(crate) use models::password_resets::*;
+pub(crate) use models::sessions::*;
+pub(crate) use models::two_factor::*;
 pub(crate) use models::users::*;
+pub(crate) use routes::registry::{Param, Resource, Returns, RouteInfo};
+pub(crate) use schemars::JsonSchema;
 pub(crate) use serde::{Deserialize, Serialize};
-pub(crate) use utils::response::Response;
+pub(crate) use utils::response::{Response, ResponseType};
 pub(crate) use utils::server_error;
 
 pub type DbPool = diesel::r2d2::Pool<diesel::r2d2::ConnectionManager<SqliteConnection>>;
@@ -1,13 +1,31 @@
-use actix_cors::Cors;
 use actix_web::middleware::Logger;
 use actix_web::web::Data;
 use actix_web::{App, HttpServer};
+use chrono::{NaiveDateTime, Utc};
 use clap::Parser;
-use krapi::cli::{Cli, SubCommand};
+use krapi::cli::{Cli, KeysAction, SnapshotAction, SubCommand};
+use krapi::config::Config;
+use krapi::middleware::{
+    parse_chaos_rule, parse_chaos_timeout, parse_delay, parse_rate_limit, Chaos, CorsOptions,
+    Delay, DelayRange, RateLimit,
+};
+use krapi::routes::auth::CookieSameSite;
+use krapi::routes::registry::Resource;
 use krapi::routes::*;
-use krapi::utils::{establish_connection, generate_users, run_migrations};
-use log::info;
+use krapi::utils::hash::{set_hash_cost, HashCost};
+use krapi::utils::{
+    ca_path, create_api_key, establish_connection, export_database, generate_users, import_users,
+    list_api_keys, list_snapshots, local_certificate, openapi_spec, parse_duration, print_docs,
+    read_users, rerun_migrations, restore_snapshot, revoke_api_key, run_migrations, rustls_config,
+    save_snapshot, seed_database, truncate_tables, typescript_definitions, ImportFormat, Lockout,
+    Preset, SigningKey, TlsFiles,
+};
+use krapi::{
+    DATABASE_ENV, DEFAULT_HOST, DEFAULT_MOCK_DATA_LEN, DEFAULT_MOCK_DATA_SINCE_STR, DEFAULT_PORT,
+};
+use log::{info, warn};
 use std::env;
+use std::path::PathBuf;
 
 #[actix_rt::main]
 async fn main() {
@@ -22,20 +40,243 @@ async fn main() {
 
     let cli = Cli::parse();
 
+    let config = match Config::load(cli.config.as_deref()) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // Every command uses the database, so `database_path` reads it from the environment
+    if let Some(database) = cli.database.or(config.database) {
+        env::set_var(DATABASE_ENV, database);
+    }
+
+    // Every command may hash passwords, so the cost is set before any of them runs
+    let default_cost = HashCost::default();
+    let hash_cost = HashCost {
+        memory: cli
+            .hash_memory
+            .or(config.hash.memory)
+            .unwrap_or(default_cost.memory),
+        iterations: cli
+            .hash_iterations
+            .or(config.hash.iterations)
+            .unwrap_or(default_cost.iterations),
+    };
+    if let Err(e) = set_hash_cost(hash_cost) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+
     match cli.subcmd {
-        SubCommand::Start { port } => {
-            start_server(port).await.unwrap();
+        SubCommand::Start {
+            host,
+            port,
+            bind,
+            unix_socket,
+            tls,
+            tls_cert,
+            tls_key,
+            session_cookie,
+            session_same_site,
+            session_secure,
+            session_domain,
+            session_ttl,
+            require_verified_email,
+            lockout_attempts,
+            lockout_ip_attempts,
+            lockout_period,
+            oidc,
+            admin_token,
+            delay,
+            chaos,
+            chaos_timeout,
+            rate_limit,
+            rate_limit_key,
+            cors_origin,
+            cors_method,
+            cors_header,
+            cors_expose_header,
+            cors_credentials,
+            cors_max_age,
+        } => {
+            let chaos = if chaos.is_empty() {
+                config
+                    .chaos
+                    .iter()
+                    .filter_map(|rule| or_config(None, "chaos", Some(rule), parse_chaos_rule))
+                    .collect()
+            } else {
+                chaos
+            };
+            let chaos_timeout = or_config(
+                chaos_timeout,
+                "chaos-timeout",
+                config.chaos_timeout.as_deref(),
+                parse_chaos_timeout,
+            )
+            .unwrap_or(std::time::Duration::from_secs(30));
+
+            let rate_limit = or_config(
+                rate_limit,
+                "rate-limit",
+                config.rate_limit.as_deref(),
+                parse_rate_limit,
+            );
+            let rate_limit_key = rate_limit_key.or(config.rate_limit_key).unwrap_or_default();
+
+            let host = host
+                .or(config.host)
+                .unwrap_or_else(|| DEFAULT_HOST.to_string());
+            let port = port.or(config.port).unwrap_or(DEFAULT_PORT as u16);
+            let unix_socket = unix_socket.or(config.unix_socket);
+
+            let bind = if bind.is_empty() { config.bind } else { bind };
+            let mut binds: Vec<String> = bind
+                .iter()
+                .map(|address| with_port(address, port))
+                .collect();
+            if binds.is_empty() && unix_socket.is_none() {
+                binds.push(with_port(&host, port));
+            }
+
+            let tls_files = match (tls_cert.or(config.tls.cert), tls_key.or(config.tls.key)) {
+                (Some(cert), Some(key)) => Some(TlsFiles { cert, key }),
+                (None, None) if tls || config.tls.enabled.unwrap_or(false) => {
+                    match local_certificate(&binds) {
+                        Ok(files) => {
+                            info!(
+                                "Trust the local CA at {} to avoid certificate warnings",
+                                ca_path().display()
+                            );
+                            Some(files)
+                        }
+                        Err(e) => {
+                            eprintln!("Failed to generate the certificate: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                (None, None) => None,
+                _ => {
+                    eprintln!("The TLS certificate and key have to be given together");
+                    std::process::exit(1);
+                }
+            };
+            let tls = tls_files.map(|files| match rustls_config(&files) {
+                Ok(config) => config,
+                Err(e) => {
+                    eprintln!("Failed to load the certificate: {}", e);
+                    std::process::exit(1);
+                }
+            });
+
+            let default_session = SessionOptions::default();
+            let session = SessionOptions {
+                cookie_name: session_cookie
+                    .or(config.session.cookie)
+                    .unwrap_or(default_session.cookie_name),
+                same_site: session_same_site
+                    .or(config.session.same_site)
+                    .unwrap_or(default_session.same_site),
+                secure: session_secure
+                    .or(config.session.secure)
+                    .unwrap_or(tls.is_some()),
+                domain: session_domain.or(config.session.domain),
+                ttl: or_config(
+                    session_ttl,
+                    "session.ttl",
+                    config.session.ttl.as_deref(),
+                    parse_duration,
+                )
+                .unwrap_or(default_session.ttl),
+            };
+            if session.same_site == CookieSameSite::None && !session.secure {
+                warn!("Browsers ignore SameSite=None cookies that are not secure. Use --tls or --session-secure true");
+            }
+
+            // Every list replaces the one from the config file, unless it is empty
+            let or_config_list = |cli: Vec<String>, config: Vec<String>| {
+                if cli.is_empty() {
+                    config
+                } else {
+                    cli
+                }
+            };
+            let cors = CorsOptions {
+                origins: or_config_list(cors_origin, config.cors.origins),
+                methods: or_config_list(cors_method, config.cors.methods),
+                headers: or_config_list(cors_header, config.cors.headers),
+                expose_headers: or_config_list(cors_expose_header, config.cors.expose_headers),
+                credentials: cors_credentials.or(config.cors.credentials),
+                max_age: cors_max_age.or(config.cors.max_age),
+            };
+            if let Err(e) = cors.validate() {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+
+            let default_lockout = Lockout::default();
+
+            let options = ServerOptions {
+                binds,
+                unix_socket,
+                tls,
+                session,
+                login: LoginOptions {
+                    require_verified_email: require_verified_email
+                        .or(config.auth.require_verified_email)
+                        .unwrap_or(false),
+                    lockout: Lockout {
+                        attempts: lockout_attempts
+                            .or(config.auth.lockout_attempts)
+                            .unwrap_or(default_lockout.attempts),
+                        ip_attempts: lockout_ip_attempts
+                            .or(config.auth.lockout_ip_attempts)
+                            .unwrap_or(default_lockout.ip_attempts),
+                        period: or_config(
+                            lockout_period,
+                            "auth.lockout-period",
+                            config.auth.lockout_period.as_deref(),
+                            parse_duration,
+                        )
+                        .unwrap_or(default_lockout.period),
+                    },
+                },
+                oidc: oidc.or(config.oidc.enabled).unwrap_or(false),
+                admin_token: AdminToken(admin_token.or(config.auth.admin_token)),
+                delay: or_config(delay, "delay", config.delay.as_deref(), parse_delay),
+                chaos: Chaos::new(chaos, chaos_timeout),
+                rate_limit: RateLimit::new(rate_limit, rate_limit_key),
+                cors,
+            };
+
+            start_server(options).await.unwrap();
         }
         SubCommand::Generate {
             len,
+            since,
             users,
             posts,
             todos,
         } => {
+            let len = len
+                .or(config.seed.len)
+                .unwrap_or(DEFAULT_MOCK_DATA_LEN as u32);
+            let since = or_config(
+                since,
+                "seed.since",
+                config.seed.since.as_deref(),
+                parse_duration,
+            )
+            .unwrap_or_else(|| parse_duration(DEFAULT_MOCK_DATA_SINCE_STR).unwrap());
+
             if users {
                 println!("Generating {} users", len);
 
-                let result = generate_users(len, establish_connection()).await;
+                let result = generate_users(len, since, establish_connection()).await;
                 if result.is_ok() {
                     println!("Generated {} users successfully :)", len);
                 }
@@ -48,56 +289,363 @@ async fn main() {
                 println!("Use --users, --posts or --todos")
             }
         }
+        SubCommand::Reset { migrations, seed } => {
+            let connection = establish_connection();
+            let mut db_connection = connection.get().unwrap();
+            run_migrations(&mut db_connection).unwrap();
+
+            if migrations {
+                println!("Running the migrations from scratch");
+                rerun_migrations(&mut db_connection).unwrap();
+            } else {
+                println!("Deleting all the data");
+                truncate_tables(&mut db_connection).unwrap();
+            }
+
+            println!("Database reset successfully :)");
+
+            if let Some(preset) = seed {
+                seed_preset(preset).await;
+            }
+        }
+        SubCommand::Seed { preset } => {
+            let connection = establish_connection();
+            run_migrations(&mut connection.get().unwrap()).unwrap();
+
+            seed_preset(preset.or(config.seed.preset).unwrap_or(Preset::Demo)).await;
+        }
+        SubCommand::Import { file, format } => {
+            let Some(format) = format.or_else(|| ImportFormat::from_path(&file)) else {
+                println!("Can't guess the format of {}", file.display());
+                println!("Use --format json or --format csv");
+                return;
+            };
+
+            let rows = match read_users(&file, format) {
+                Ok(rows) => rows,
+                Err(e) => {
+                    println!("Failed to read {}: {}", file.display(), e);
+                    return;
+                }
+            };
+
+            println!("Importing {} users from {}", rows.len(), file.display());
+
+            let connection = establish_connection();
+            let mut db_connection = connection.get().unwrap();
+            run_migrations(&mut db_connection).unwrap();
+
+            match import_users(rows, &mut db_connection) {
+                Ok(Ok(imported)) => println!("Imported {} users successfully :)", imported),
+                Ok(Err(errors)) => {
+                    for error in &errors {
+                        println!("{}", error);
+                    }
+                    println!(
+                        "Nothing was imported. Fix the {} rows above and try again",
+                        errors.len()
+                    );
+                }
+                Err(e) => println!("Failed to import: {}", e),
+            }
+        }
+        SubCommand::Export { format, out } => {
+            let connection = establish_connection();
+            let mut db_connection = connection.get().unwrap();
+            run_migrations(&mut db_connection).unwrap();
+
+            match export_database(format, out.as_deref(), &mut db_connection) {
+                Ok(_) => {
+                    if let Some(out) = out {
+                        println!("Exported the users to {} :)", out.display());
+                    }
+                }
+                Err(e) => eprintln!("Failed to export: {}", e),
+            }
+        }
+        SubCommand::Snapshot { action } => match action {
+            SnapshotAction::Save { name } => {
+                let connection = establish_connection();
+                run_migrations(&mut connection.get().unwrap()).unwrap();
+
+                match save_snapshot(&name) {
+                    Ok(_) => println!("Saved snapshot {} :)", name),
+                    Err(e) => println!("Failed to save snapshot: {}", e),
+                }
+            }
+            SnapshotAction::Restore { name } => match restore_snapshot(&name) {
+                Ok(_) => println!("Restored snapshot {} :)", name),
+                Err(e) => println!("Failed to restore snapshot: {}", e),
+            },
+            SnapshotAction::List => match list_snapshots() {
+                Ok(snapshots) if snapshots.is_empty() => println!("No snapshots saved yet"),
+                Ok(snapshots) => {
+                    for snapshot in snapshots {
+                        println!(
+                            "{}\t{}\t{} bytes",
+                            snapshot.name, snapshot.created_at, snapshot.size
+                        );
+                    }
+                }
+                Err(e) => println!("Failed to list snapshots: {}", e),
+            },
+        },
+        SubCommand::Keys { action } => {
+            let connection = establish_connection();
+            let mut db_connection = connection.get().unwrap();
+            run_migrations(&mut db_connection).unwrap();
+
+            match action {
+                KeysAction::Create {
+                    name,
+                    scopes,
+                    expires_in,
+                } => {
+                    let expires_at =
+                        expires_in.map(|expires_in| Utc::now().naive_utc() + expires_in);
+
+                    match create_api_key(&name, &scopes, expires_at, &mut db_connection) {
+                        Ok((key, _)) => {
+                            println!("Created API key {} :)", name);
+                            println!("{}", key);
+                            println!("Send it in the X-API-Key header. It won't be shown again.");
+                        }
+                        Err(e) => println!("Failed to create the API key: {}", e),
+                    }
+                }
+                KeysAction::List => match list_api_keys(&mut db_connection) {
+                    Ok(keys) if keys.is_empty() => println!("No API keys yet"),
+                    Ok(keys) => {
+                        let date = |date: Option<NaiveDateTime>, none: &str| {
+                            date.map_or(none.to_string(), |date| {
+                                date.format("%Y-%m-%d %H:%M:%S").to_string()
+                            })
+                        };
+
+                        println!("ID\tNAME\tKEY\tSCOPES\tCREATED\tEXPIRES\tLAST USED");
+                        for key in keys {
+                            println!(
+                                "{}\t{}\t{}...\t{}\t{}\t{}\t{}",
+                                key.id,
+                                key.name,
+                                key.prefix,
+                                key.scopes,
+                                key.created_at,
+                                date(key.expires_at, "never"),
+                                date(key.last_used_at, "never"),
+                            );
+                        }
+                    }
+                    Err(e) => println!("Failed to list the API keys: {}", e),
+                },
+                KeysAction::Revoke { id } => match revoke_api_key(id, &mut db_connection) {
+                    Ok(true) => println!("Revoked API key {} :)", id),
+                    Ok(false) => println!("There is no API key with the id {}", id),
+                    Err(e) => println!("Failed to revoke the API key: {}", e),
+                },
+            }
+        }
+        SubCommand::Openapi { host, port } => {
+            let host = host
+                .or(config.host)
+                .unwrap_or_else(|| DEFAULT_HOST.to_string());
+            let port = port.or(config.port).unwrap_or(DEFAULT_PORT as u16);
+            let server_url = format!("http://{}", with_port(&host, port));
+            let spec = openapi_spec(&server_url);
+
+            println!("{}", serde_json::to_string_pretty(&spec).unwrap());
+        }
+        SubCommand::Typescript { out } => {
+            let definitions = typescript_definitions();
+
+            match out {
+                Some(out) => match std::fs::write(&out, definitions) {
+                    Ok(_) => println!("Wrote the definitions to {} :)", out.display()),
+                    Err(e) => println!("Failed to write {}: {}", out.display(), e),
+                },
+                None => print!("{}", definitions),
+            }
+        }
         SubCommand::Docs {
             users,
+            auth,
+            admin,
             posts,
             todos,
         } => {
-            println!("Show docs");
-            if users {
-                println!("Show docs for users");
+            let all = !(users || auth || admin || posts || todos);
+
+            if users || all {
+                print_docs(Resource::Users);
+            }
+            if auth || all {
+                print_docs(Resource::Auth);
+            }
+            if all {
+                print_docs(Resource::Oidc);
+                print_docs(Resource::Mailbox);
+            }
+            if admin || all {
+                print_docs(Resource::Admin);
+            }
+            if all {
+                print_docs(Resource::Greet);
+                print_docs(Resource::Docs);
             }
             if posts {
-                println!("Show docs for posts");
+                println!("There are no routes for posts yet");
             }
             if todos {
-                println!("Show docs for todos");
+                println!("There are no routes for todos yet");
             }
         }
     }
 }
 
-async fn start_server(port: u16) -> std::io::Result<()> {
+async fn seed_preset(preset: Preset) {
+    println!("Seeding the {:?} preset", preset);
+
+    if seed_database(preset, establish_connection()).await.is_ok() {
+        println!("Seeded successfully :)");
+    }
+}
+
+/// Use the value from the command line, or else parse the one from the config file
+///
+/// Exits if the config file has an invalid value.
+fn or_config<T>(
+    value: Option<T>,
+    key: &str,
+    config_value: Option<&str>,
+    parser: fn(&str) -> Result<T, String>,
+) -> Option<T> {
+    value.or_else(|| match Config::parse(key, config_value, parser) {
+        Ok(value) => value,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    })
+}
+
+/// Add the port to an address that has none, like `0.0.0.0` or `::1`
+fn with_port(address: &str, port: u16) -> String {
+    let has_port = match address.strip_prefix('[') {
+        Some(rest) => rest.contains("]:"),
+        None => address.matches(':').count() == 1,
+    };
+
+    if has_port {
+        address.to_string()
+    } else if address.contains(':') && !address.starts_with('[') {
+        // An IPv6 address needs brackets before the port
+        format!("[{}]:{}", address, port)
+    } else {
+        format!("{}:{}", address, port)
+    }
+}
+
+/// The settings of `krapi start`, after merging the command line, the environment and the config file
+struct ServerOptions {
+    /// The TCP addresses to listen on, like `localhost:8090`
+    binds: Vec<String>,
+    unix_socket: Option<PathBuf>,
+    /// Serve the TCP addresses over HTTPS
+    tls: Option<rustls::ServerConfig>,
+    session: SessionOptions,
+    login: LoginOptions,
+    /// Serve the routes of the OpenID Connect provider
+    oidc: bool,
+    admin_token: AdminToken,
+    delay: Option<DelayRange>,
+    chaos: Chaos,
+    rate_limit: RateLimit,
+    cors: CorsOptions,
+}
+
+async fn start_server(options: ServerOptions) -> std::io::Result<()> {
+    let ServerOptions {
+        binds,
+        unix_socket,
+        tls,
+        session,
+        login,
+        oidc,
+        admin_token,
+        delay,
+        chaos,
+        rate_limit,
+        cors,
+    } = options;
+
     // Get the connection
     let connection = establish_connection();
 
     // run the pending migrations
     run_migrations(&mut connection.get().unwrap()).unwrap();
 
-    info!("Starting server at: http://localhost:{}", port);
+    // Signs the ID tokens of the OpenID Connect provider
+    let signing_key = Data::new(SigningKey::generate().map_err(std::io::Error::other)?);
 
-    HttpServer::new(move || {
+    let mut server = HttpServer::new(move || {
         App::new()
+            .wrap(chaos.clone())
+            .wrap(Delay::new(delay))
+            .wrap(rate_limit.clone())
             .wrap(Logger::default())
-            .wrap(
-                Cors::default()
-                    .allow_any_origin()
-                    .allow_any_header()
-                    .allow_any_method()
-                    .supports_credentials(),
-            )
+            .wrap(cors.to_cors())
             .app_data(Data::new(connection.clone()))
-            // users
-            .service(get_users_route)
-            .service(create_user_route)
-            .service(match_user_route)
-            .service(update_password_route)
-            .service(update_user_route)
-            .service(get_user_route)
-            // greet
-            .service(greet_route)
-    })
-    .bind(&format!("localhost:{}", port))?
-    .run()
-    .await
+            .app_data(Data::new(admin_token.clone()))
+            .app_data(Data::new(session.clone()))
+            .app_data(Data::new(login.clone()))
+            .app_data(signing_key.clone())
+            .configure(|cfg| configure_routes(cfg, oidc))
+    });
+
+    let scheme = if tls.is_some() { "https" } else { "http" };
+
+    for address in &binds {
+        server = match &tls {
+            Some(tls) => server.bind_rustls(address, tls.clone())?,
+            None => server.bind(address)?,
+        };
+        info!("Starting server at: {}://{}", scheme, address);
+    }
+
+    if let Some(socket) = &unix_socket {
+        #[cfg(unix)]
+        {
+            remove_stale_socket(socket)?;
+            server = server.bind_uds(socket)?;
+        }
+
+        #[cfg(not(unix))]
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "Unix domain sockets are only supported on Unix",
+        ));
+
+        info!("Starting server at: unix:{}", socket.display());
+    }
+
+    if let Some(address) = binds.first() {
+        info!("API explorer at: {}://{}/docs", scheme, address);
+        info!("Guide at: {}://{}/guide", scheme, address);
+    }
+
+    server.run().await
+}
+
+/// A socket left behind by a previous run can't be bound again
+#[cfg(unix)]
+fn remove_stale_socket(socket: &std::path::Path) -> std::io::Result<()> {
+    use std::os::unix::fs::FileTypeExt;
+
+    if let Ok(metadata) = std::fs::symlink_metadata(socket) {
+        if metadata.file_type().is_socket() {
+            std::fs::remove_file(socket)?;
+        }
+    }
+
+    Ok(())
 }
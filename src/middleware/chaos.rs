@@ -0,0 +1,295 @@
+use crate::utils::parse_duration;
+use crate::*;
+use actix_web::body::{self, BoxBody, EitherBody, MessageBody, SizedStream};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::StatusCode;
+use actix_web::Error;
+use futures_util::stream::{self, StreamExt};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::future::{ready, Future, Ready};
+use std::io;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The ways a request can fail
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// Respond with `500` and [`ResponseType::ServerError`]
+    Error,
+    /// Hang, then respond with `504`
+    Timeout,
+    /// Close the connection in the middle of the body
+    Drop,
+    /// Respond with a body that is not valid JSON
+    Malformed,
+}
+
+/// Which requests fail, how often and how
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChaosRule {
+    /// The paths the rule applies to, like `/get-user/*`. `None` means every path.
+    pub pattern: Option<String>,
+    /// How many of the requests fail, from `0.0` to `1.0`
+    pub rate: f64,
+    /// One of these is picked for every failing request
+    pub faults: Vec<Fault>,
+}
+
+/// Parse a rule like `10%`, `25%:timeout` or `/get-user/*=50%:error,drop`
+///
+/// The format is `[PATTERN=]RATE%[:FAULTS]`. The faults are `error`, `timeout`, `drop` and `malformed`, separated by commas.
+/// If no fault is given, `error` is used.
+///
+/// ## Example
+///
+/// ```
+/// # use krapi::middleware::{parse_chaos_rule, Fault};
+/// #
+/// let rule = parse_chaos_rule("/get-user/*=50%:error,drop").unwrap();
+/// assert_eq!(rule.pattern.as_deref(), Some("/get-user/*"));
+/// assert_eq!(rule.rate, 0.5);
+/// assert_eq!(rule.faults, vec![Fault::Error, Fault::Drop]);
+/// ```
+pub fn parse_chaos_rule(value: &str) -> Result<ChaosRule, String> {
+    let (pattern, rest) = match value.split_once('=') {
+        Some((pattern, rest)) => (Some(pattern.trim().to_string()), rest),
+        None => (None, value),
+    };
+
+    let (rate, faults) = match rest.split_once(':') {
+        Some((rate, faults)) => (rate, Some(faults)),
+        None => (rest, None),
+    };
+
+    let rate = rate
+        .trim()
+        .trim_end_matches('%')
+        .parse::<f64>()
+        .ok()
+        .filter(|rate| (0.0..=100.0).contains(rate))
+        .ok_or_else(|| format!("Invalid rate `{}`. Expected something like `10%`", rate))?
+        / 100.0;
+
+    let faults = match faults {
+        Some(faults) => faults
+            .split(',')
+            .map(|fault| match fault.trim() {
+                "error" => Ok(Fault::Error),
+                "timeout" => Ok(Fault::Timeout),
+                "drop" => Ok(Fault::Drop),
+                "malformed" => Ok(Fault::Malformed),
+                fault => Err(format!(
+                    "Unknown fault `{}`. Use one of: error, timeout, drop, malformed",
+                    fault
+                )),
+            })
+            .collect::<Result<Vec<Fault>, String>>()?,
+        None => vec![Fault::Error],
+    };
+
+    Ok(ChaosRule {
+        pattern,
+        rate,
+        faults,
+    })
+}
+
+impl ChaosRule {
+    fn matches(&self, path: &str) -> bool {
+        match &self.pattern {
+            Some(pattern) => matches_pattern(pattern, path),
+            None => true,
+        }
+    }
+}
+
+/// Match a path against a pattern where `*` matches anything
+fn matches_pattern(pattern: &str, path: &str) -> bool {
+    match pattern.split_once('*') {
+        Some((prefix, rest)) => {
+            let Some(path) = path.strip_prefix(prefix) else {
+                return false;
+            };
+
+            // Try every possible length for the `*`
+            (0..=path.len())
+                .filter(|i| path.is_char_boundary(*i))
+                .any(|i| matches_pattern(rest, &path[i..]))
+        }
+        None => pattern == path,
+    }
+}
+
+/// Middleware that makes some of the requests fail on purpose
+///
+/// Use it to exercise retry logic and error boundaries. Rules with a pattern are checked before the rules without one,
+/// so a route can have its own failure rate.
+#[derive(Debug, Clone, Default)]
+pub struct Chaos {
+    rules: Arc<Vec<ChaosRule>>,
+    timeout: Duration,
+}
+
+impl Chaos {
+    /// `timeout` is how long a [`Fault::Timeout`] hangs before responding
+    pub fn new(mut rules: Vec<ChaosRule>, timeout: Duration) -> Self {
+        // Route specific rules first
+        rules.sort_by_key(|rule| rule.pattern.is_none());
+
+        Self {
+            rules: Arc::new(rules),
+            timeout,
+        }
+    }
+
+    /// Pick the fault for a request, if it should fail
+    fn pick(&self, path: &str) -> Option<Fault> {
+        let rule = self.rules.iter().find(|rule| rule.matches(path))?;
+        let mut rng = rand::thread_rng();
+
+        if rng.gen::<f64>() < rule.rate {
+            rule.faults.choose(&mut rng).copied()
+        } else {
+            None
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Chaos
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = ChaosMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ChaosMiddleware {
+            service: Rc::new(service),
+            chaos: self.clone(),
+        }))
+    }
+}
+
+pub struct ChaosMiddleware<S> {
+    service: Rc<S>,
+    chaos: Chaos,
+}
+
+impl<S, B> Service<ServiceRequest> for ChaosMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let fault = self.chaos.pick(req.path());
+        let timeout = self.chaos.timeout;
+        let service = self.service.clone();
+
+        Box::pin(async move {
+            let Some(fault) = fault else {
+                return Ok(service.call(req).await?.map_into_left_body());
+            };
+
+            log::info!("Injecting {:?} into {} {}", fault, req.method(), req.path());
+
+            match fault {
+                Fault::Error => {
+                    let response = Response::server_error()
+                        .msg("Injected failure")
+                        .send_with_status(StatusCode::INTERNAL_SERVER_ERROR);
+
+                    Ok(req.into_response(response).map_into_right_body())
+                }
+                Fault::Timeout => {
+                    actix_rt::time::sleep(timeout).await;
+
+                    let response = Response::server_error()
+                        .msg("Injected timeout")
+                        .send_with_status(StatusCode::GATEWAY_TIMEOUT);
+
+                    Ok(req.into_response(response).map_into_right_body())
+                }
+                Fault::Drop | Fault::Malformed => {
+                    // Let the route respond, then break its body
+                    let response = service.call(req).await?;
+                    let (req, response) = response.into_parts();
+                    let (head, body) = response.into_parts();
+
+                    let bytes = body::to_bytes(body).await.unwrap_or_default();
+                    let half = bytes.slice(..bytes.len() / 2);
+
+                    let broken_body = if fault == Fault::Drop {
+                        // Promise the whole body but fail after half of it, so the connection is closed.
+                        // The pause lets the first half reach the client before that.
+                        let chunks = stream::once(ready(Ok(half))).chain(stream::once(async {
+                            actix_rt::time::sleep(Duration::from_millis(50)).await;
+                            Err(io::Error::other("Injected drop"))
+                        }));
+                        BoxBody::new(SizedStream::new(bytes.len() as u64, Box::pin(chunks)))
+                    } else {
+                        BoxBody::new(half)
+                    };
+
+                    let response = head.set_body(broken_body);
+
+                    Ok(ServiceResponse::new(req, response).map_into_right_body())
+                }
+            }
+        })
+    }
+}
+
+/// Parse the `--chaos-timeout` value
+pub fn parse_chaos_timeout(value: &str) -> Result<Duration, String> {
+    parse_duration(value)?
+        .to_std()
+        .map_err(|_| "The timeout can't be negative".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, web, App, HttpResponse};
+
+    #[actix_web::test]
+    async fn route_rules_come_first() {
+        let rules = vec![
+            parse_chaos_rule("0%").unwrap(),
+            parse_chaos_rule("/fail/*=100%").unwrap(),
+            parse_chaos_rule("/broken=100%:malformed").unwrap(),
+        ];
+        let app = test::init_service(
+            App::new()
+                .wrap(Chaos::new(rules, Duration::ZERO))
+                .default_service(web::to(|| async { HttpResponse::Ok().body(r#"{"a":1}"#) })),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/ok").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = test::TestRequest::get().uri("/fail/1").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let req = test::TestRequest::get().uri("/broken").to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        assert_eq!(body, r#"{"a"#);
+    }
+}
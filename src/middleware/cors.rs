@@ -0,0 +1,139 @@
+use crate::*;
+use actix_cors::Cors;
+use actix_web::http::header::HeaderName;
+use actix_web::http::{Method, Uri};
+use std::str::FromStr;
+
+/// Headers the browser can always read, so clients can back off when they are rate limited
+const EXPOSED_HEADERS: [&str; 4] = [
+    "Retry-After",
+    "X-RateLimit-Limit",
+    "X-RateLimit-Remaining",
+    "X-RateLimit-Reset",
+];
+
+/// Which cross-origin requests the server accepts
+///
+/// An empty list allows anything. It is also the `[cors]` table of the config file.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct CorsOptions {
+    /// The origins that can call the server, like `http://localhost:3000`
+    pub origins: Vec<String>,
+    /// The methods they can use, like `GET`
+    pub methods: Vec<String>,
+    /// The request headers they can send
+    pub headers: Vec<String>,
+    /// The response headers they can read, besides the rate limit headers
+    pub expose_headers: Vec<String>,
+    /// Allow cookies and the `Authorization` header. On by default
+    pub credentials: Option<bool>,
+    /// How many seconds the browser can cache a preflight response
+    pub max_age: Option<usize>,
+}
+
+impl CorsOptions {
+    /// Check the origins, methods and headers, so a typo is reported at startup
+    ///
+    /// An origin is a scheme and a host, with an optional port and no path, like `http://localhost:3000`.
+    ///
+    /// ```
+    /// # use krapi::middleware::CorsOptions;
+    /// #
+    /// let cors = CorsOptions {
+    ///     origins: vec!["http://localhost:3000".to_string()],
+    ///     methods: vec!["GET".to_string()],
+    ///     ..CorsOptions::default()
+    /// };
+    /// assert!(cors.validate().is_ok());
+    ///
+    /// let cors = CorsOptions {
+    ///     origins: vec!["localhost:3000/".to_string()],
+    ///     ..CorsOptions::default()
+    /// };
+    /// assert!(cors.validate().is_err());
+    /// ```
+    pub fn validate(&self) -> Result<(), String> {
+        for origin in self.origins.iter().filter(|origin| *origin != "*") {
+            let is_valid =
+                Uri::from_str(origin).is_ok_and(|uri| match (uri.scheme_str(), uri.authority()) {
+                    (Some(scheme), Some(authority)) => {
+                        format!("{}://{}", scheme, authority) == *origin
+                    }
+                    _ => false,
+                });
+
+            if !is_valid {
+                return Err(format!(
+                    "Invalid CORS origin `{}`. Expected something like `http://localhost:3000`",
+                    origin
+                ));
+            }
+        }
+
+        for method in &self.methods {
+            if Method::from_bytes(method.as_bytes()).is_err() {
+                return Err(format!("Invalid CORS method `{}`", method));
+            }
+        }
+
+        for header in self.headers.iter().chain(&self.expose_headers) {
+            if HeaderName::from_str(header).is_err() {
+                return Err(format!("Invalid CORS header `{}`", header));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Build the middleware
+    ///
+    /// Call [`validate`](Self::validate) first: on an invalid origin, method or header, actix-cors only
+    /// logs it and the server workers fail to start.
+    ///
+    /// Browsers reject `Access-Control-Allow-Origin: *` on credentialed requests,
+    /// so when any origin is allowed, the origin of the request is echoed back instead.
+    /// The same goes for the allowed headers of a preflight request.
+    pub fn to_cors(&self) -> Cors {
+        let credentials = self.credentials.unwrap_or(true);
+
+        let mut cors = Cors::default();
+
+        cors = if self.origins.is_empty() || self.origins.iter().any(|origin| origin == "*") {
+            let cors = cors.allow_any_origin();
+            if credentials {
+                cors
+            } else {
+                cors.send_wildcard()
+            }
+        } else {
+            self.origins
+                .iter()
+                .fold(cors, |cors, origin| cors.allowed_origin(origin))
+        };
+
+        cors = if self.methods.is_empty() {
+            cors.allow_any_method()
+        } else {
+            cors.allowed_methods(self.methods.iter().map(|method| method.as_str()))
+        };
+
+        cors = if self.headers.is_empty() {
+            cors.allow_any_header()
+        } else {
+            cors.allowed_headers(self.headers.iter().map(|header| header.as_str()))
+        };
+
+        cors = cors.expose_headers(
+            EXPOSED_HEADERS
+                .into_iter()
+                .chain(self.expose_headers.iter().map(|header| header.as_str())),
+        );
+
+        if credentials {
+            cors = cors.supports_credentials();
+        }
+
+        cors.max_age(self.max_age)
+    }
+}
@@ -0,0 +1,242 @@
+use crate::utils::parse_duration;
+use crate::*;
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::StatusCode;
+use actix_web::Error;
+use rand::Rng;
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::time::Duration;
+
+/// How long to hold a response before sending it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DelayRange {
+    pub min: Duration,
+    pub max: Duration,
+}
+
+impl DelayRange {
+    /// The header that overrides the delay of a single request
+    pub const HEADER: &'static str = "X-Krapi-Delay";
+
+    /// The longest delay the header can ask for
+    pub const MAX_HEADER_DELAY: Duration = Duration::from_secs(60);
+
+    /// Pick a random delay within the range
+    pub fn sample(&self) -> Duration {
+        if self.min >= self.max {
+            return self.min;
+        }
+
+        rand::thread_rng().gen_range(self.min..=self.max)
+    }
+}
+
+/// Parse a delay like `500ms` or a range like `200..800ms`
+///
+/// If the start of the range has no unit, it uses the unit of the end. `0` means no delay.
+///
+/// ## Example
+///
+/// ```
+/// # use krapi::middleware::parse_delay;
+/// # use std::time::Duration;
+/// #
+/// let range = parse_delay("200..800ms").unwrap();
+/// assert_eq!(range.min, Duration::from_millis(200));
+/// assert_eq!(range.max, Duration::from_millis(800));
+///
+/// let range = parse_delay("1s").unwrap();
+/// assert_eq!(range.min, range.max);
+/// ```
+pub fn parse_delay(value: &str) -> Result<DelayRange, String> {
+    let value = value.trim();
+
+    if value == "0" {
+        return Ok(DelayRange {
+            min: Duration::ZERO,
+            max: Duration::ZERO,
+        });
+    }
+
+    let (min, max) = match value.split_once("..") {
+        Some((min, max)) => {
+            // `200..800ms` means `200ms..800ms`
+            let min = if min.chars().all(|c| c.is_ascii_digit()) {
+                let unit = max.trim_start_matches(|c: char| c.is_ascii_digit());
+                format!("{}{}", min, unit)
+            } else {
+                min.to_string()
+            };
+
+            (
+                to_std(parse_duration(&min)?)?,
+                to_std(parse_duration(max)?)?,
+            )
+        }
+        None => {
+            let delay = to_std(parse_duration(value)?)?;
+            (delay, delay)
+        }
+    };
+
+    if min > max {
+        return Err(format!(
+            "The start of the delay `{}` is after its end",
+            value
+        ));
+    }
+
+    Ok(DelayRange { min, max })
+}
+
+fn to_std(duration: chrono::Duration) -> Result<Duration, String> {
+    duration
+        .to_std()
+        .map_err(|_| "The delay can't be negative".to_string())
+}
+
+/// Middleware that holds every response for a while before sending it
+///
+/// Frontends need to see their loading states and race conditions, which a local server answering instantly hides.
+///
+/// A request can set its own delay with the `X-Krapi-Delay` header, like `X-Krapi-Delay: 1500ms`.
+/// The header can ask for up to a minute. An invalid or longer delay is rejected with status 400.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Delay {
+    range: Option<DelayRange>,
+}
+
+impl Delay {
+    /// Delay every response by a random amount within the range. If `None`, only the header delays responses.
+    pub fn new(range: Option<DelayRange>) -> Self {
+        Self { range }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Delay
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = DelayMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(DelayMiddleware {
+            service,
+            range: self.range,
+        }))
+    }
+}
+
+pub struct DelayMiddleware<S> {
+    service: S,
+    range: Option<DelayRange>,
+}
+
+impl<S, B> Service<ServiceRequest> for DelayMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        // The header wins over the server wide delay
+        let header = req
+            .headers()
+            .get(DelayRange::HEADER)
+            .map(|value| match value.to_str() {
+                Ok(value) => parse_header_delay(value),
+                Err(_) => Err(format!(
+                    "The {} header isn't valid text",
+                    DelayRange::HEADER
+                )),
+            });
+
+        let range = match header {
+            Some(Ok(range)) => Some(range),
+            Some(Err(message)) => {
+                let response = Response::invalid_input()
+                    .msg(&message)
+                    .send_with_status(StatusCode::BAD_REQUEST);
+
+                return Box::pin(ready(Ok(req.into_response(response).map_into_right_body())));
+            }
+            None => self.range,
+        };
+
+        let response = self.service.call(req);
+
+        Box::pin(async move {
+            let response = response.await?;
+
+            if let Some(range) = range {
+                actix_rt::time::sleep(range.sample()).await;
+            }
+
+            Ok(response.map_into_left_body())
+        })
+    }
+}
+
+/// Parse the delay of the header, which can't be longer than [`DelayRange::MAX_HEADER_DELAY`]
+fn parse_header_delay(value: &str) -> Result<DelayRange, String> {
+    let range =
+        parse_delay(value).map_err(|e| format!("Invalid {} header: {}", DelayRange::HEADER, e))?;
+
+    if range.max > DelayRange::MAX_HEADER_DELAY {
+        return Err(format!(
+            "The {} header can't be longer than {} seconds",
+            DelayRange::HEADER,
+            DelayRange::MAX_HEADER_DELAY.as_secs()
+        ));
+    }
+
+    Ok(range)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, web, App, HttpResponse};
+
+    #[actix_web::test]
+    async fn rejects_an_oversized_header() {
+        let app = test::init_service(
+            App::new()
+                .wrap(Delay::new(None))
+                .route("/", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        for value in ["999999999999999d", "2m", "soon"] {
+            let req = test::TestRequest::get()
+                .uri("/")
+                .insert_header((DelayRange::HEADER, value))
+                .to_request();
+            let res = test::call_service(&app, req).await;
+
+            assert_eq!(res.status(), StatusCode::BAD_REQUEST, "{}", value);
+        }
+
+        let req = test::TestRequest::get()
+            .uri("/")
+            .insert_header((DelayRange::HEADER, "10ms"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+}
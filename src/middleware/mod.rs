@@ -0,0 +1,9 @@
+mod chaos;
+mod cors;
+mod delay;
+mod rate_limit;
+
+pub use chaos::{parse_chaos_rule, parse_chaos_timeout, Chaos, ChaosRule, Fault};
+pub use cors::CorsOptions;
+pub use delay::{parse_delay, Delay, DelayRange};
+pub use rate_limit::{parse_rate_limit, RateLimit, RateLimitKey, RateLimitRule};
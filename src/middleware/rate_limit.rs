@@ -0,0 +1,348 @@
+use crate::routes::{ApiClient, SessionOptions};
+use crate::schema::sessions;
+use crate::utils::{find_api_key, parse_duration};
+use crate::*;
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{HeaderName, HeaderValue, AUTHORIZATION};
+use actix_web::http::StatusCode;
+use actix_web::Error;
+use std::collections::HashMap;
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How many requests a client can make in a period
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitRule {
+    pub requests: u32,
+    pub per: Duration,
+}
+
+/// Parse a limit like `60/m`, `10/s` or `100/15m`
+///
+/// ## Example
+///
+/// ```
+/// # use krapi::middleware::parse_rate_limit;
+/// # use std::time::Duration;
+/// #
+/// let rule = parse_rate_limit("100/15m").unwrap();
+/// assert_eq!(rule.requests, 100);
+/// assert_eq!(rule.per, Duration::from_secs(15 * 60));
+/// ```
+pub fn parse_rate_limit(value: &str) -> Result<RateLimitRule, String> {
+    let (requests, per) = value.split_once('/').ok_or_else(|| {
+        format!(
+            "Invalid rate limit `{}`. Expected something like `60/m`",
+            value
+        )
+    })?;
+
+    let requests = requests
+        .trim()
+        .parse::<u32>()
+        .ok()
+        .filter(|requests| *requests > 0)
+        .ok_or_else(|| format!("Invalid number of requests `{}`", requests))?;
+
+    // `60/m` means `60/1m`
+    let per = per.trim();
+    let per = if per.starts_with(|c: char| c.is_ascii_digit()) {
+        parse_duration(per)?
+    } else {
+        parse_duration(&format!("1{}", per))?
+    };
+
+    let per = per
+        .to_std()
+        .ok()
+        .filter(|per| !per.is_zero())
+        .ok_or_else(|| "The period must be longer than zero".to_string())?;
+
+    Ok(RateLimitRule { requests, per })
+}
+
+/// What identifies a client
+#[derive(clap::ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitKey {
+    /// The IP address of the client
+    #[default]
+    Ip,
+    /// The session of the `Authorization` header or the session cookie, or the key of the `X-API-Key` header.
+    /// Clients without a valid one are limited by IP
+    Token,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Middleware that limits how many requests each client can make
+///
+/// Uses a token bucket: every client starts with `requests` tokens, every request takes one,
+/// and the tokens refill evenly over `per`. When there are no tokens left, it responds with
+/// `429 Too Many Requests` and [`ResponseType::TooManyRequests`].
+///
+/// Every response has the `X-RateLimit-Limit`, `X-RateLimit-Remaining` and `X-RateLimit-Reset` headers.
+/// Limited responses also have `Retry-After`.
+#[derive(Clone)]
+pub struct RateLimit {
+    rule: Option<RateLimitRule>,
+    key: RateLimitKey,
+    buckets: Arc<Mutex<HashMap<String, Bucket>>>,
+}
+
+impl RateLimit {
+    /// If `rule` is `None`, nothing is limited
+    pub fn new(rule: Option<RateLimitRule>, key: RateLimitKey) -> Self {
+        Self {
+            rule,
+            key,
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Identify the client of a request, like `session:3`, `key:1` or `ip:127.0.0.1`
+    ///
+    /// A token only counts once it belongs to a live session or API key, so a client can't get
+    /// a fresh bucket for every made up token.
+    async fn client_key(&self, req: &ServiceRequest) -> String {
+        if self.key == RateLimitKey::Token {
+            if let Some(client) = token_client(req).await {
+                return client;
+            }
+        }
+
+        // Not `realip_remote_addr`, or a client could dodge the limit with `X-Forwarded-For`
+        match req.peer_addr() {
+            Some(addr) => format!("ip:{}", addr.ip()),
+            None => "ip:unknown".to_string(),
+        }
+    }
+
+    /// Take a token from the client's bucket
+    ///
+    /// Returns the state of the bucket after the request.
+    fn take(&self, rule: RateLimitRule, key: String) -> Limit {
+        let capacity = rule.requests as f64;
+        let refill_per_sec = capacity / rule.per.as_secs_f64();
+        let now = Instant::now();
+
+        let mut buckets = self.buckets.lock().unwrap();
+
+        // Forget the clients that are back to a full bucket, so the map doesn't grow forever
+        if buckets.len() > 10_000 {
+            buckets.retain(|_, bucket| {
+                bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * refill_per_sec
+                    < capacity
+            });
+        }
+
+        let bucket = buckets.entry(key).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+
+        // Refill for the time since the last request
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * refill_per_sec).min(capacity);
+        bucket.updated = now;
+
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+
+        Limit {
+            allowed,
+            limit: rule.requests,
+            remaining: bucket.tokens.floor() as u32,
+            // Until the next token
+            retry_after: ((1.0 - bucket.tokens).max(0.0) / refill_per_sec).ceil() as u64,
+            // Until the bucket is full again
+            reset: ((capacity - bucket.tokens) / refill_per_sec).ceil() as u64,
+        }
+    }
+}
+
+/// The live session or API key of the request's token, if it has one
+async fn token_client(req: &ServiceRequest) -> Option<String> {
+    let pool = req.app_data::<web::Data<DbPool>>()?.clone();
+
+    let api_key = req
+        .headers()
+        .get(ApiClient::HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(|key| key.trim().to_string());
+
+    let cookie_name = req
+        .app_data::<web::Data<SessionOptions>>()
+        .map(|options| options.cookie_name.clone())
+        .unwrap_or_else(|| SessionOptions::default().cookie_name);
+    let session_token = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string())
+        .or_else(|| {
+            req.cookie(&cookie_name)
+                .map(|cookie| cookie.value().to_string())
+        });
+
+    if api_key.is_none() && session_token.is_none() {
+        return None;
+    }
+
+    let client = web::block(move || {
+        let mut db_connection = pool.get().unwrap();
+
+        if let Some(key) = api_key {
+            if let Some(key) = find_api_key(&key, &mut db_connection)? {
+                return Ok(Some(format!("key:{}", key.id)));
+            }
+        }
+
+        if let Some(token) = session_token {
+            let session = sessions::table
+                .filter(sessions::token.eq(&token))
+                .filter(sessions::expires_at.gt(chrono::Utc::now().naive_utc()))
+                .select(sessions::id)
+                .first::<i32>(&mut db_connection)
+                .optional()?;
+
+            if let Some(id) = session {
+                return Ok(Some(format!("session:{}", id)));
+            }
+        }
+
+        Ok::<Option<String>, diesel::result::Error>(None)
+    });
+
+    match client.await {
+        Ok(client_result) => match client_result {
+            Ok(client) => client,
+            Err(e) => {
+                server_error(e);
+                None
+            }
+        },
+        Err(e) => {
+            server_error(e);
+            None
+        }
+    }
+}
+
+struct Limit {
+    allowed: bool,
+    limit: u32,
+    remaining: u32,
+    retry_after: u64,
+    reset: u64,
+}
+
+impl Limit {
+    fn headers(&self) -> [(HeaderName, HeaderValue); 3] {
+        [
+            (
+                HeaderName::from_static("x-ratelimit-limit"),
+                HeaderValue::from(self.limit),
+            ),
+            (
+                HeaderName::from_static("x-ratelimit-remaining"),
+                HeaderValue::from(self.remaining),
+            ),
+            (
+                HeaderName::from_static("x-ratelimit-reset"),
+                HeaderValue::from(self.reset),
+            ),
+        ]
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RateLimitMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitMiddleware {
+            service: Rc::new(service),
+            rate_limit: self.clone(),
+        }))
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: Rc<S>,
+    rate_limit: RateLimit,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let Some(rule) = self.rate_limit.rule else {
+            let response = self.service.call(req);
+            return Box::pin(async move { Ok(response.await?.map_into_left_body()) });
+        };
+
+        let rate_limit = self.rate_limit.clone();
+        let service = self.service.clone();
+
+        Box::pin(async move {
+            let key = rate_limit.client_key(&req).await;
+            let limit = rate_limit.take(rule, key);
+
+            if !limit.allowed {
+                let mut response = Response::too_many_requests()
+                    .msg(&format!(
+                        "Too many requests. Try again in {} seconds",
+                        limit.retry_after
+                    ))
+                    .send_with_status(StatusCode::TOO_MANY_REQUESTS);
+
+                let headers = response.headers_mut();
+                headers.insert(
+                    HeaderName::from_static("retry-after"),
+                    HeaderValue::from(limit.retry_after),
+                );
+                for (name, value) in limit.headers() {
+                    headers.insert(name, value);
+                }
+
+                return Ok(req.into_response(response).map_into_right_body());
+            }
+
+            let mut response = service.call(req).await?;
+
+            for (name, value) in limit.headers() {
+                response.headers_mut().insert(name, value);
+            }
+
+            Ok(response.map_into_left_body())
+        })
+    }
+}
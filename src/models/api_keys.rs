@@ -0,0 +1,67 @@
+use crate::schema::*;
+use chrono::NaiveDateTime;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Clone)]
+pub struct ApiKey {
+    pub id: i32,
+    pub name: String,
+    /// The start of the key, to tell the keys apart
+    pub prefix: String,
+    /// SHA-256 of the key, in hex
+    pub key_hash: String,
+    /// Space separated, like `admin users:read`
+    pub scopes: String,
+    pub created_at: NaiveDateTime,
+    /// `None` if it never expires
+    pub expires_at: Option<NaiveDateTime>,
+    pub last_used_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Insertable, Clone)]
+#[diesel(table_name = api_keys)]
+pub struct ApiKeyNew {
+    pub name: String,
+    pub prefix: String,
+    pub key_hash: String,
+    pub scopes: String,
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+/// An API key, without the key itself
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct ApiKeyJson {
+    pub id: i32,
+    pub name: String,
+    pub prefix: String,
+    pub scopes: Vec<String>,
+    pub created_at: NaiveDateTime,
+    pub expires_at: Option<NaiveDateTime>,
+    pub last_used_at: Option<NaiveDateTime>,
+}
+
+impl ApiKey {
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.split(' ').any(|s| s == scope)
+    }
+}
+
+impl From<ApiKey> for ApiKeyJson {
+    fn from(key: ApiKey) -> Self {
+        Self {
+            id: key.id,
+            name: key.name,
+            prefix: key.prefix,
+            scopes: key
+                .scopes
+                .split(' ')
+                .filter(|scope| !scope.is_empty())
+                .map(str::to_string)
+                .collect(),
+            created_at: key.created_at,
+            expires_at: key.expires_at,
+            last_used_at: key.last_used_at,
+        }
+    }
+}
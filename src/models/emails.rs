@@ -0,0 +1,22 @@
+use crate::schema::*;
+use chrono::NaiveDateTime;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// An email in the local mailbox
+#[derive(Debug, Serialize, Deserialize, Queryable, Clone, JsonSchema)]
+pub struct Email {
+    pub id: i32,
+    pub recipient: String,
+    pub subject: String,
+    pub body: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, Insertable, Clone)]
+#[diesel(table_name = emails)]
+pub struct EmailNew {
+    pub recipient: String,
+    pub subject: String,
+    pub body: String,
+}
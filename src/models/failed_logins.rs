@@ -0,0 +1,38 @@
+use crate::schema::*;
+use chrono::NaiveDateTime;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Clone)]
+pub struct FailedLogin {
+    pub id: i32,
+    /// `None` when the username doesn't exist
+    pub user_id: Option<i32>,
+    pub ip: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, Insertable, Clone)]
+#[diesel(table_name = failed_logins)]
+pub struct FailedLoginNew {
+    pub user_id: Option<i32>,
+    pub ip: String,
+    /// Set explicitly, because `CURRENT_TIMESTAMP` only has seconds
+    pub created_at: NaiveDateTime,
+}
+
+/// How many logins failed lately. Sent with a failed login, to show a captcha after a few of them
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, JsonSchema)]
+pub struct LoginAttemptsJson {
+    pub failed_attempts: u32,
+    /// How many more can fail before the account is locked. `null` if it is never locked
+    pub remaining_attempts: Option<u32>,
+}
+
+/// When a locked account can log in again
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, JsonSchema)]
+pub struct LockoutJson {
+    pub locked_until: NaiveDateTime,
+    /// Seconds until then, like the `Retry-After` header
+    pub retry_after: i64,
+}
@@ -1 +1,9 @@
+pub mod api_keys;
+pub mod email_verifications;
+pub mod emails;
+pub mod failed_logins;
+pub mod oidc;
+pub mod password_resets;
+pub mod sessions;
+pub mod two_factor;
 pub mod users;
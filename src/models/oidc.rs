@@ -0,0 +1,154 @@
+use crate::schema::*;
+use chrono::NaiveDateTime;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Clone)]
+pub struct AuthorizationCode {
+    pub id: i32,
+    pub code: String,
+    pub client_id: String,
+    pub redirect_uri: String,
+    pub user_id: i32,
+    /// Space separated, like `openid profile email`
+    pub scope: String,
+    pub nonce: Option<String>,
+    pub code_challenge: Option<String>,
+    /// `S256` or `plain`
+    pub code_challenge_method: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, Insertable, Clone)]
+#[diesel(table_name = authorization_codes)]
+pub struct AuthorizationCodeNew {
+    pub code: String,
+    pub client_id: String,
+    pub redirect_uri: String,
+    pub user_id: i32,
+    pub scope: String,
+    pub nonce: Option<String>,
+    pub code_challenge: Option<String>,
+    pub code_challenge_method: Option<String>,
+    pub expires_at: NaiveDateTime,
+}
+
+/// An access token from `/token`. Only `/userinfo` accepts it
+#[derive(Debug, Serialize, Deserialize, Queryable, Clone)]
+pub struct OidcAccessToken {
+    pub id: i32,
+    pub token: String,
+    pub client_id: String,
+    pub user_id: i32,
+    /// The scope that was granted, space separated
+    pub scope: String,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, Insertable, Clone)]
+#[diesel(table_name = oidc_access_tokens)]
+pub struct OidcAccessTokenNew {
+    pub token: String,
+    pub client_id: String,
+    pub user_id: i32,
+    pub scope: String,
+    pub expires_at: NaiveDateTime,
+}
+
+/// The OpenID Connect discovery document
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct OpenIdConfiguration {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub userinfo_endpoint: String,
+    pub jwks_uri: String,
+    pub response_types_supported: Vec<String>,
+    pub response_modes_supported: Vec<String>,
+    pub grant_types_supported: Vec<String>,
+    pub subject_types_supported: Vec<String>,
+    pub id_token_signing_alg_values_supported: Vec<String>,
+    pub scopes_supported: Vec<String>,
+    pub token_endpoint_auth_methods_supported: Vec<String>,
+    pub code_challenge_methods_supported: Vec<String>,
+    pub claims_supported: Vec<String>,
+}
+
+/// A successful response of `/token`
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct TokenJson {
+    /// Only works for `/userinfo`
+    pub access_token: String,
+    /// Always `Bearer`
+    pub token_type: String,
+    /// Seconds until the access token expires
+    pub expires_in: i64,
+    /// Only when the `openid` scope was asked for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id_token: Option<String>,
+    pub scope: String,
+}
+
+/// An error of `/token`, as OAuth 2.0 describes it
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct OAuthErrorJson {
+    /// Like `invalid_grant`
+    pub error: String,
+    pub error_description: String,
+}
+
+/// The claims about a user, in `/userinfo` and in the ID token
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct UserInfoJson {
+    /// The id of the user
+    pub sub: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preferred_username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email_verified: Option<bool>,
+}
+
+/// The claims of an ID token
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IdTokenClaims {
+    pub iss: String,
+    /// The client id
+    pub aud: String,
+    pub iat: i64,
+    pub exp: i64,
+    pub auth_time: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    #[serde(flatten)]
+    pub user: UserInfoJson,
+}
+
+/// The public keys that check the signature of ID tokens
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct Jwks {
+    pub keys: Vec<Jwk>,
+}
+
+/// A P-256 public key
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct Jwk {
+    /// Always `EC`
+    pub kty: String,
+    /// Always `P-256`
+    pub crv: String,
+    /// The x coordinate, base64url encoded
+    pub x: String,
+    /// The y coordinate, base64url encoded
+    pub y: String,
+    pub kid: String,
+    #[serde(rename = "use")]
+    pub key_use: String,
+    /// Always `ES256`
+    pub alg: String,
+}
@@ -0,0 +1,20 @@
+use crate::schema::*;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Clone)]
+pub struct PasswordReset {
+    pub id: i32,
+    pub token: String,
+    pub user_id: i32,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, Insertable, Clone)]
+#[diesel(table_name = password_resets)]
+pub struct PasswordResetNew {
+    pub token: String,
+    pub user_id: i32,
+    pub expires_at: NaiveDateTime,
+}
@@ -0,0 +1,33 @@
+use crate::schema::*;
+use chrono::NaiveDateTime;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Clone)]
+pub struct Session {
+    pub id: i32,
+    pub token: String,
+    pub csrf_token: String,
+    pub user_id: i32,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, Insertable, Clone)]
+#[diesel(table_name = sessions)]
+pub struct SessionNew {
+    pub token: String,
+    pub csrf_token: String,
+    pub user_id: i32,
+    pub expires_at: NaiveDateTime,
+}
+
+/// What a client gets after logging in
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct SessionJson {
+    /// Send it in the `Authorization: Bearer <token>` header. Not sent when the session is in a cookie
+    pub token: Option<String>,
+    /// Send it in the `X-CSRF-Token` header of state-changing requests. Only sent when the session is in a cookie
+    pub csrf_token: Option<String>,
+    pub expires_at: NaiveDateTime,
+}
@@ -0,0 +1,53 @@
+use crate::schema::*;
+use chrono::NaiveDateTime;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Clone)]
+pub struct TwoFactor {
+    pub user_id: i32,
+    /// Base32, like authenticator apps show it
+    pub secret: String,
+    /// `None` until the first code is confirmed
+    pub enabled_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, Insertable, Clone)]
+#[diesel(table_name = two_factor)]
+pub struct TwoFactorNew {
+    pub user_id: i32,
+    pub secret: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Clone)]
+pub struct RecoveryCode {
+    pub id: i32,
+    pub user_id: i32,
+    pub code_hash: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, Insertable, Clone)]
+#[diesel(table_name = recovery_codes)]
+pub struct RecoveryCodeNew {
+    pub user_id: i32,
+    pub code_hash: String,
+}
+
+/// What a client gets when it starts enrolling in two-factor authentication
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct TwoFactorEnrollment {
+    /// For typing into the authenticator app by hand
+    pub secret: String,
+    /// The `otpauth://` URI the QR code contains
+    pub otpauth_uri: String,
+    /// The QR code as an SVG image
+    pub qr_svg: String,
+}
+
+/// Single-use codes that replace the authenticator app when it is lost
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct RecoveryCodesJson {
+    pub recovery_codes: Vec<String>,
+}
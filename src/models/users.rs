@@ -1,5 +1,6 @@
 use crate::schema::*;
 use chrono::NaiveDateTime;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize, Queryable, Clone)]
@@ -12,9 +13,10 @@ pub struct User {
     pub phone: Option<f64>,
     pub password: String,
     pub created_at: NaiveDateTime,
+    pub email_verified_at: Option<NaiveDateTime>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Insertable, Clone)]
+#[derive(Debug, Serialize, Deserialize, Insertable, Clone, JsonSchema)]
 #[diesel(table_name = users)]
 pub struct UserNew {
     pub name: String,
@@ -25,7 +27,7 @@ pub struct UserNew {
     pub password: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct UserJson {
     pub id: i32,
     pub name: String,
@@ -34,4 +36,35 @@ pub struct UserJson {
     pub img_url: Option<String>,
     pub phone: Option<f64>,
     pub created_at: NaiveDateTime,
+    /// When the user clicked the link in the verification email. `null` if they haven't yet
+    pub email_verified_at: Option<NaiveDateTime>,
+}
+
+/// A new user with an explicit creation time. Used when seeding the database.
+#[derive(Debug, Serialize, Deserialize, Insertable, Clone)]
+#[diesel(table_name = users)]
+pub struct UserSeed {
+    pub name: String,
+    pub username: String,
+    pub email: Option<String>,
+    pub img_url: Option<String>,
+    pub phone: Option<f64>,
+    pub password: String,
+    pub created_at: NaiveDateTime,
+    pub email_verified_at: Option<NaiveDateTime>,
+}
+
+impl From<User> for UserJson {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id,
+            name: user.name,
+            username: user.username,
+            email: user.email,
+            img_url: user.img_url,
+            phone: user.phone,
+            created_at: user.created_at,
+            email_verified_at: user.email_verified_at,
+        }
+    }
 }
@@ -0,0 +1,71 @@
+use crate::routes::ApiClient;
+use crate::utils::{list_snapshots, Snapshot};
+use crate::*;
+
+use super::AdminToken;
+
+/// List the saved database snapshots
+#[get("/__admin/snapshots")]
+pub async fn route(
+    req: HttpRequest,
+    admin_token: Data<AdminToken>,
+    client: Option<ApiClient>,
+) -> HttpResponse {
+    if let Err(response) = admin_token.verify(&req, client.as_ref()) {
+        return response;
+    }
+
+    match web::block(list_snapshots).await {
+        Ok(snapshots_result) => match snapshots_result {
+            Ok(snapshots) => Response::success().data(snapshots).send(),
+            Err(e) => {
+                server_error(e);
+                Response::server_error().send()
+            }
+        },
+        Err(e) => {
+            server_error(e);
+            Response::server_error().send()
+        }
+    }
+}
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "listSnapshots",
+    resource: Resource::Admin,
+    method: "GET",
+    path: "/__admin/snapshots",
+    summary: "List the saved database snapshots",
+    description: "Returns the snapshots saved with `krapi snapshot save` or the save snapshot route, oldest first.",
+    params: &[],
+    query: &[],
+    headers: super::HEADERS,
+    body: None,
+    body_schema: None,
+    form: false,
+    data_schema: Some(|gen| gen.subschema_for::<Vec<Snapshot>>()),
+    returns: &[
+        Returns {
+            response_type: ResponseType::Success,
+            description: "with the data `Snapshot[]`",
+        },
+        super::ADMIN_RETURNS[0],
+        super::ADMIN_RETURNS[1],
+        super::ADMIN_RETURNS[2],
+    ],
+    example_body: None,
+    example_response: r#"{
+   "type": "Success",
+   "msg": null,
+   "data": [
+     {
+       "name": "checkout-edge-case",
+       "size": 28672,
+       "created_at": "2023-05-21T07:30:48"
+     }
+   ]
+}"#,
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
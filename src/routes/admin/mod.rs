@@ -0,0 +1,84 @@
+pub mod list_snapshots;
+pub mod reset;
+pub mod restore_snapshot;
+pub mod save_snapshot;
+
+use crate::routes::ApiClient;
+use crate::utils::ADMIN_SCOPE;
+use crate::*;
+
+/// The header every admin route needs
+const HEADERS: &[Param] = &[Param {
+    name: AdminToken::HEADER,
+    description: "The token given to `--admin-token`. Not needed with an `X-API-Key` that has the `admin` scope",
+    example: "secret",
+}];
+
+/// Responses every admin route can send
+const ADMIN_RETURNS: [Returns; 3] = [
+    Returns {
+        response_type: ResponseType::NoPermission,
+        description: "if the admin routes are disabled",
+    },
+    Returns {
+        response_type: ResponseType::Unauthorized,
+        description: "if the admin token is missing or incorrect, and there is no API key with the `admin` scope",
+    },
+    Returns {
+        response_type: ResponseType::ServerError,
+        description: "if any error occurs",
+    },
+];
+
+/// The token that guards the `/__admin` routes
+///
+/// The admin routes are disabled unless the server was started with `--admin-token`.
+/// Every admin request must send the same token in the `X-Krapi-Admin-Token` header.
+///
+/// An API key with the `admin` scope in the `X-API-Key` header works too, even without `--admin-token`.
+#[derive(Clone, Debug, Default)]
+pub struct AdminToken(pub Option<String>);
+
+impl AdminToken {
+    /// The header that carries the admin token
+    pub const HEADER: &'static str = "X-Krapi-Admin-Token";
+
+    /// Check the admin token, or the API key, of the request
+    ///
+    /// If the request is not allowed, returns the response that should be sent instead.
+    pub fn verify(
+        &self,
+        req: &HttpRequest,
+        client: Option<&ApiClient>,
+    ) -> Result<(), HttpResponse> {
+        if let Some(client) = client {
+            if client.key.has_scope(ADMIN_SCOPE) {
+                return Ok(());
+            }
+            if !req.headers().contains_key(Self::HEADER) {
+                return client.require_scope(ADMIN_SCOPE);
+            }
+        }
+
+        let Some(token) = &self.0 else {
+            return Err(Response::no_permission()
+                .msg(
+                    "Admin routes are disabled. Start the server with --admin-token to enable them",
+                )
+                .send());
+        };
+
+        let given = req
+            .headers()
+            .get(Self::HEADER)
+            .and_then(|value| value.to_str().ok());
+
+        if given != Some(token.as_str()) {
+            return Err(Response::unauthorized()
+                .msg("Missing or incorrect admin token")
+                .send());
+        }
+
+        Ok(())
+    }
+}
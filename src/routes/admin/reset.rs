@@ -0,0 +1,184 @@
+use crate::routes::ApiClient;
+use crate::utils::{rerun_migrations, seed_database, truncate_tables, Preset};
+use crate::*;
+use actix_web::http::StatusCode;
+
+use super::AdminToken;
+
+#[derive(Deserialize, Default, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ResetOptions {
+    #[serde(default)]
+    migrations: bool,
+    seed: Option<Preset>,
+}
+
+/// Reset the database
+#[post("/__admin/reset")]
+pub async fn route(
+    req: HttpRequest,
+    pool: Data<DbPool>,
+    admin_token: Data<AdminToken>,
+    client: Option<ApiClient>,
+    body: web::Bytes,
+) -> HttpResponse {
+    if let Err(response) = admin_token.verify(&req, client.as_ref()) {
+        return response;
+    }
+
+    // An empty body means the defaults. Anything else has to be valid, so a typo doesn't wipe the database
+    let options = if body.iter().all(u8::is_ascii_whitespace) {
+        ResetOptions::default()
+    } else {
+        match serde_json::from_slice::<ResetOptions>(&body) {
+            Ok(options) => options,
+            Err(e) => {
+                return Response::invalid_input()
+                    .msg(&format!("Invalid body: {}", e))
+                    .send_with_status(StatusCode::BAD_REQUEST);
+            }
+        }
+    };
+
+    // Delete the data
+    let reset = web::block({
+        let mut db_connection = pool.get().unwrap();
+
+        move || {
+            if options.migrations {
+                rerun_migrations(&mut db_connection).map_err(|e| e.to_string())
+            } else {
+                truncate_tables(&mut db_connection).map_err(|e| e.to_string())
+            }
+        }
+    });
+
+    match reset.await {
+        Ok(reset_result) => {
+            if let Err(e) = reset_result {
+                server_error(e);
+                return Response::server_error().send();
+            }
+        }
+        Err(e) => {
+            server_error(e);
+            return Response::server_error().send();
+        }
+    }
+
+    // Load the preset
+    if let Some(preset) = options.seed {
+        if seed_database(preset, pool.get_ref().clone()).await.is_err() {
+            return Response::server_error().send();
+        }
+    }
+
+    Response::success().msg("Database reset successful").send()
+}
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "resetDatabase",
+    resource: Resource::Admin,
+    method: "POST",
+    path: "/__admin/reset",
+    summary: "Reset the database",
+    description: "Deletes all the data, so end-to-end tests can start every test from a known state without restarting the server. If `migrations` is true, the tables are dropped and the migrations run again. If `seed` is given, that preset is loaded after the reset. The body is optional, but a body that isn't valid is rejected with `400`.",
+    params: &[],
+    query: &[],
+    headers: super::HEADERS,
+    body: Some(
+        r#"{
+   "migrations": boolean (optional),
+   "seed": "empty" | "small" | "demo" (optional)
+}"#,
+    ),
+    body_schema: Some(|gen| gen.subschema_for::<ResetOptions>()),
+    form: false,
+    data_schema: None,
+    returns: &[
+        Returns {
+            response_type: ResponseType::Success,
+            description: "if the database was reset",
+        },
+        Returns {
+            response_type: ResponseType::InvalidInput,
+            description: "with the status `400`, if the body is not valid. Nothing is deleted",
+        },
+        super::ADMIN_RETURNS[0],
+        super::ADMIN_RETURNS[1],
+        super::ADMIN_RETURNS[2],
+    ],
+    example_body: Some(r#"{ "seed": "demo" }"#),
+    example_response: r#"{
+   "type": "Success",
+   "msg": "Database reset successful"
+}"#,
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_pool;
+    use actix_web::{test, App};
+
+    #[actix_web::test]
+    async fn rejects_an_invalid_body() {
+        let pool = test_pool();
+        diesel::insert_into(crate::schema::users::table)
+            .values(&UserNew {
+                name: "Shanto".to_string(),
+                username: "shanto".to_string(),
+                email: None,
+                img_url: None,
+                phone: None,
+                password: String::new(),
+            })
+            .execute(&mut pool.get().unwrap())
+            .unwrap();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(pool.clone()))
+                .app_data(Data::new(AdminToken(Some("secret".to_string()))))
+                .service(route),
+        )
+        .await;
+
+        for body in [
+            r#"{ "seed": "demoo" }"#,
+            r#"{ "migration": true }"#,
+            "seed=demo",
+        ] {
+            let req = test::TestRequest::post()
+                .uri("/__admin/reset")
+                .insert_header((AdminToken::HEADER, "secret"))
+                .set_payload(body)
+                .to_request();
+            let res = test::call_service(&app, req).await;
+            assert_eq!(res.status(), StatusCode::BAD_REQUEST, "{}", body);
+        }
+
+        let users = crate::schema::users::table
+            .count()
+            .get_result::<i64>(&mut pool.get().unwrap())
+            .unwrap();
+        assert_eq!(users, 1);
+
+        // No body resets with the defaults
+        let req = test::TestRequest::post()
+            .uri("/__admin/reset")
+            .insert_header((AdminToken::HEADER, "secret"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let users = crate::schema::users::table
+            .count()
+            .get_result::<i64>(&mut pool.get().unwrap())
+            .unwrap();
+        assert_eq!(users, 0);
+    }
+}
@@ -0,0 +1,79 @@
+use crate::routes::ApiClient;
+use crate::utils::{restore_snapshot, snapshot_exists};
+use crate::*;
+
+use super::AdminToken;
+
+/// Restore a snapshot of the database
+#[post("/__admin/snapshots/{name}/restore")]
+pub async fn route(
+    req: HttpRequest,
+    admin_token: Data<AdminToken>,
+    client: Option<ApiClient>,
+    path: Path<String>,
+) -> HttpResponse {
+    if let Err(response) = admin_token.verify(&req, client.as_ref()) {
+        return response;
+    }
+
+    let name = path.into_inner();
+
+    if !snapshot_exists(&name) {
+        return Response::not_found().msg("Snapshot not found").send();
+    }
+
+    match web::block(move || restore_snapshot(&name)).await {
+        Ok(restore_result) => match restore_result {
+            Ok(_) => Response::success().msg("Snapshot restored").send(),
+            Err(e) => {
+                server_error(e);
+                Response::server_error().send()
+            }
+        },
+        Err(e) => {
+            server_error(e);
+            Response::server_error().send()
+        }
+    }
+}
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "restoreSnapshot",
+    resource: Resource::Admin,
+    method: "POST",
+    path: "/__admin/snapshots/{name}/restore",
+    summary: "Restore a snapshot of the database",
+    description: "All the current data is replaced with the data of the snapshot, except the API keys, which are kept. The pending migrations run afterwards, so snapshots from older versions work.",
+    params: &[Param {
+        name: "name",
+        description: "The name of the snapshot.",
+        example: "checkout-edge-case",
+    }],
+    query: &[],
+    headers: super::HEADERS,
+    body: None,
+    body_schema: None,
+    form: false,
+    data_schema: None,
+    returns: &[
+        Returns {
+            response_type: ResponseType::Success,
+            description: "if the snapshot was restored",
+        },
+        Returns {
+            response_type: ResponseType::NotFound,
+            description: "if the snapshot does not exist",
+        },
+        super::ADMIN_RETURNS[0],
+        super::ADMIN_RETURNS[1],
+        super::ADMIN_RETURNS[2],
+    ],
+    example_body: None,
+    example_response: r#"{
+   "type": "Success",
+   "msg": "Snapshot restored"
+}"#,
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
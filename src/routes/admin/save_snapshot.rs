@@ -0,0 +1,89 @@
+use crate::routes::ApiClient;
+use crate::utils::{is_valid_snapshot_name, save_snapshot, Snapshot};
+use crate::*;
+
+use super::AdminToken;
+
+/// Save a snapshot of the database
+#[post("/__admin/snapshots/{name}")]
+pub async fn route(
+    req: HttpRequest,
+    admin_token: Data<AdminToken>,
+    client: Option<ApiClient>,
+    path: Path<String>,
+) -> HttpResponse {
+    if let Err(response) = admin_token.verify(&req, client.as_ref()) {
+        return response;
+    }
+
+    let name = path.into_inner();
+
+    if !is_valid_snapshot_name(&name) {
+        return Response::invalid_input()
+            .msg("Snapshot name can only contain letters, digits, - and _")
+            .send();
+    }
+
+    match web::block(move || save_snapshot(&name)).await {
+        Ok(snapshot_result) => match snapshot_result {
+            Ok(snapshot) => Response::success()
+                .msg("Snapshot saved")
+                .data(snapshot)
+                .send(),
+            Err(e) => {
+                server_error(e);
+                Response::server_error().send()
+            }
+        },
+        Err(e) => {
+            server_error(e);
+            Response::server_error().send()
+        }
+    }
+}
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "saveSnapshot",
+    resource: Resource::Admin,
+    method: "POST",
+    path: "/__admin/snapshots/{name}",
+    summary: "Save a snapshot of the database",
+    description: "An existing snapshot with the same name is overwritten.",
+    params: &[Param {
+        name: "name",
+        description: "The name of the snapshot. Only letters, digits, `-` and `_` are allowed.",
+        example: "checkout-edge-case",
+    }],
+    query: &[],
+    headers: super::HEADERS,
+    body: None,
+    body_schema: None,
+    form: false,
+    data_schema: Some(|gen| gen.subschema_for::<Snapshot>()),
+    returns: &[
+        Returns {
+            response_type: ResponseType::Success,
+            description: "with the data `Snapshot`",
+        },
+        Returns {
+            response_type: ResponseType::InvalidInput,
+            description: "if the name is invalid",
+        },
+        super::ADMIN_RETURNS[0],
+        super::ADMIN_RETURNS[1],
+        super::ADMIN_RETURNS[2],
+    ],
+    example_body: None,
+    example_response: r#"{
+   "type": "Success",
+   "msg": "Snapshot saved",
+   "data": {
+     "name": "checkout-edge-case",
+     "size": 28672,
+     "created_at": "2023-05-21T07:30:48"
+   }
+}"#,
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
@@ -0,0 +1,62 @@
+use crate::*;
+
+use super::ApiClient;
+
+/// Get the API key of the request
+#[get("/api-key")]
+pub async fn route(client: ApiClient) -> HttpResponse {
+    Response::success()
+        .data(ApiKeyJson::from(client.key))
+        .send()
+}
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "apiKey",
+    resource: Resource::Auth,
+    method: "GET",
+    path: "/api-key",
+    summary: "Get the API key of the request",
+    description: "Lets a machine client check that its key works. Returns the name, scopes and expiry of the key in the `X-API-Key` header. Keys are made with `krapi keys create`.",
+    params: &[],
+    query: &[],
+    headers: &[Param {
+        name: ApiClient::HEADER,
+        description: "A key from `krapi keys create`",
+        example: "krapi_Xo2pLr8QeT5sVb1nKc7MzW4yHg9dFa3U4bV1mQ8x",
+    }],
+    body: None,
+    body_schema: None,
+    form: false,
+    data_schema: Some(|gen| gen.subschema_for::<ApiKeyJson>()),
+    returns: &[
+        Returns {
+            response_type: ResponseType::Success,
+            description: "with the data `ApiKeyJson`",
+        },
+        Returns {
+            response_type: ResponseType::Unauthorized,
+            description: "if the key is missing, invalid or expired",
+        },
+        Returns {
+            response_type: ResponseType::ServerError,
+            description: "if any error occurs",
+        },
+    ],
+    example_body: None,
+    example_response: r#"{
+   "type": "Success",
+   "msg": null,
+   "data": {
+     "id": 1,
+     "name": "bff",
+     "prefix": "krapi_Xo2pLr",
+     "scopes": ["admin"],
+     "created_at": "2023-05-21T07:30:48",
+     "expires_at": null,
+     "last_used_at": "2023-05-21T07:35:12"
+   }
+}"#,
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
@@ -0,0 +1,162 @@
+use crate::schema::{recovery_codes, two_factor};
+use crate::utils::hash::hash_password;
+use crate::utils::{check_totp_code, generate_recovery_codes, totp};
+use crate::*;
+use chrono::Utc;
+use std::error::Error;
+
+use super::AuthUser;
+
+#[derive(Deserialize, Clone, JsonSchema)]
+pub struct TwoFactorCode {
+    /// The code from the authenticator app
+    pub code: String,
+}
+
+/// Whether the code confirmed the enrollment
+enum Confirmation {
+    NotEnrolled,
+    AlreadyEnabled,
+    IncorrectCode,
+    Enabled(Vec<String>),
+}
+
+/// Enable two-factor authentication
+#[post("/confirm-2fa")]
+pub async fn route(
+    pool: web::Data<DbPool>,
+    auth: AuthUser,
+    item: web::Json<TwoFactorCode>,
+) -> HttpResponse {
+    let code = item.into_inner().code;
+
+    let confirm = web::block({
+        let mut db_connection = pool.get().unwrap();
+
+        move || -> Result<Confirmation, Box<dyn Error + Send + Sync + 'static>> {
+            let Some(enrollment) = two_factor::table
+                .filter(two_factor::user_id.eq(auth.user.id))
+                .first::<TwoFactor>(&mut db_connection)
+                .optional()?
+            else {
+                return Ok(Confirmation::NotEnrolled);
+            };
+
+            if enrollment.enabled_at.is_some() {
+                return Ok(Confirmation::AlreadyEnabled);
+            }
+
+            if !check_totp_code(&totp(&enrollment.secret, &auth.user.username)?, &code) {
+                return Ok(Confirmation::IncorrectCode);
+            }
+
+            let codes = generate_recovery_codes();
+            let new_codes = codes
+                .iter()
+                .map(|code| RecoveryCodeNew {
+                    user_id: auth.user.id,
+                    code_hash: hash_password(code),
+                })
+                .collect::<Vec<RecoveryCodeNew>>();
+
+            db_connection.transaction(|connection| {
+                diesel::update(two_factor::table.filter(two_factor::user_id.eq(auth.user.id)))
+                    .set(two_factor::enabled_at.eq(Utc::now().naive_utc()))
+                    .execute(connection)?;
+
+                diesel::delete(
+                    recovery_codes::table.filter(recovery_codes::user_id.eq(auth.user.id)),
+                )
+                .execute(connection)?;
+                diesel::insert_into(recovery_codes::table)
+                    .values(&new_codes)
+                    .execute(connection)?;
+
+                Ok::<(), diesel::result::Error>(())
+            })?;
+
+            Ok(Confirmation::Enabled(codes))
+        }
+    });
+
+    match confirm.await {
+        Ok(confirm_result) => match confirm_result {
+            Ok(Confirmation::Enabled(recovery_codes)) => Response::success()
+                .msg("Two-factor authentication is enabled")
+                .data(RecoveryCodesJson { recovery_codes })
+                .send(),
+            Ok(Confirmation::NotEnrolled) => Response::not_found()
+                .msg("Start enrolling with /enroll-2fa first")
+                .send(),
+            Ok(Confirmation::AlreadyEnabled) => Response::already_exists()
+                .msg("Two-factor authentication is already enabled")
+                .send(),
+            Ok(Confirmation::IncorrectCode) => Response::incorrect_password()
+                .msg("The code is incorrect")
+                .send(),
+            Err(e) => {
+                server_error(e);
+                Response::server_error().send()
+            }
+        },
+        Err(e) => {
+            server_error(e);
+            Response::server_error().send()
+        }
+    }
+}
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "confirm2fa",
+    resource: Resource::Auth,
+    method: "POST",
+    path: "/confirm-2fa",
+    summary: "Enable two-factor authentication",
+    description: "Checks a code from the authenticator app against the secret from `/enroll-2fa`, enables two-factor authentication and returns the recovery codes. From then on `/verify-user` needs a code too. Each recovery code works once, and they are only shown this time.",
+    params: &[],
+    query: &[],
+    headers: &super::HEADERS,
+    body: Some(
+        r#"{
+    "code": string
+}"#,
+    ),
+    body_schema: Some(|gen| gen.subschema_for::<TwoFactorCode>()),
+    form: false,
+    data_schema: Some(|gen| gen.subschema_for::<RecoveryCodesJson>()),
+    returns: &[
+        Returns {
+            response_type: ResponseType::Success,
+            description: "with the data `RecoveryCodesJson`",
+        },
+        Returns {
+            response_type: ResponseType::NotFound,
+            description: "if the user hasn't started enrolling",
+        },
+        Returns {
+            response_type: ResponseType::AlreadyExists,
+            description: "if two-factor authentication is already enabled",
+        },
+        Returns {
+            response_type: ResponseType::IncorrectPassword,
+            description: "if the code is incorrect",
+        },
+        super::AUTH_RETURNS[0],
+        super::AUTH_RETURNS[1],
+    ],
+    example_body: Some(
+        r#"{
+  "code": "492039"
+}"#,
+    ),
+    example_response: r#"{
+   "type": "Success",
+   "msg": "Two-factor authentication is enabled",
+   "data": {
+     "recovery_codes": ["k3v9x-2mq7p", "a8d2r-w5n0e", "..."]
+   }
+}"#,
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
@@ -0,0 +1,109 @@
+use crate::schema::{recovery_codes, two_factor};
+use crate::*;
+use std::error::Error;
+
+use super::confirm_2fa::TwoFactorCode;
+use super::{check_second_factor, AuthUser, SecondFactor};
+
+/// Disable two-factor authentication
+#[post("/disable-2fa")]
+pub async fn route(
+    pool: web::Data<DbPool>,
+    auth: AuthUser,
+    item: web::Json<TwoFactorCode>,
+) -> HttpResponse {
+    let code = item.into_inner().code;
+
+    let disable = web::block({
+        let mut db_connection = pool.get().unwrap();
+
+        move || -> Result<SecondFactor, Box<dyn Error + Send + Sync + 'static>> {
+            let second_factor = check_second_factor(&auth.user, Some(&code), &mut db_connection)?;
+
+            if second_factor == SecondFactor::Correct {
+                db_connection.transaction(|connection| {
+                    diesel::delete(two_factor::table.filter(two_factor::user_id.eq(auth.user.id)))
+                        .execute(connection)?;
+                    diesel::delete(
+                        recovery_codes::table.filter(recovery_codes::user_id.eq(auth.user.id)),
+                    )
+                    .execute(connection)?;
+
+                    Ok::<(), diesel::result::Error>(())
+                })?;
+            }
+
+            Ok(second_factor)
+        }
+    });
+
+    match disable.await {
+        Ok(disable_result) => match disable_result {
+            Ok(SecondFactor::Correct) => Response::success()
+                .msg("Two-factor authentication is disabled")
+                .send(),
+            Ok(SecondFactor::NotEnabled) => Response::not_found()
+                .msg("Two-factor authentication is not enabled")
+                .send(),
+            Ok(SecondFactor::Missing | SecondFactor::Incorrect) => Response::incorrect_password()
+                .msg("The code is incorrect")
+                .send(),
+            Err(e) => {
+                server_error(e);
+                Response::server_error().send()
+            }
+        },
+        Err(e) => {
+            server_error(e);
+            Response::server_error().send()
+        }
+    }
+}
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "disable2fa",
+    resource: Resource::Auth,
+    method: "POST",
+    path: "/disable-2fa",
+    summary: "Disable two-factor authentication",
+    description: "Needs a code from the authenticator app or a recovery code. Deletes the secret and the recovery codes.",
+    params: &[],
+    query: &[],
+    headers: &super::HEADERS,
+    body: Some(
+        r#"{
+    "code": string
+}"#,
+    ),
+    body_schema: Some(|gen| gen.subschema_for::<TwoFactorCode>()),
+    form: false,
+    data_schema: None,
+    returns: &[
+        Returns {
+            response_type: ResponseType::Success,
+            description: "if two-factor authentication was disabled",
+        },
+        Returns {
+            response_type: ResponseType::NotFound,
+            description: "if two-factor authentication is not enabled",
+        },
+        Returns {
+            response_type: ResponseType::IncorrectPassword,
+            description: "if the code is incorrect",
+        },
+        super::AUTH_RETURNS[0],
+        super::AUTH_RETURNS[1],
+    ],
+    example_body: Some(
+        r#"{
+  "code": "492039"
+}"#,
+    ),
+    example_response: r#"{
+   "type": "Success",
+   "msg": "Two-factor authentication is disabled"
+}"#,
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
@@ -0,0 +1,100 @@
+use crate::schema::two_factor;
+use crate::utils::{generate_totp_secret, qr_svg, totp};
+use crate::*;
+use std::error::Error;
+
+use super::AuthUser;
+
+/// Start enrolling in two-factor authentication
+#[post("/enroll-2fa")]
+pub async fn route(pool: web::Data<DbPool>, auth: AuthUser) -> HttpResponse {
+    let enroll = web::block({
+        let mut db_connection = pool.get().unwrap();
+
+        move || -> Result<Option<TwoFactorEnrollment>, Box<dyn Error + Send + Sync + 'static>> {
+            let existing = two_factor::table
+                .filter(two_factor::user_id.eq(auth.user.id))
+                .first::<TwoFactor>(&mut db_connection)
+                .optional()?;
+
+            if existing.is_some_and(|existing| existing.enabled_at.is_some()) {
+                return Ok(None);
+            }
+
+            let secret = generate_totp_secret();
+            let otpauth_uri = totp(&secret, &auth.user.username)?.get_url();
+            let qr_svg = qr_svg(&otpauth_uri)?;
+
+            diesel::replace_into(two_factor::table)
+                .values(&TwoFactorNew {
+                    user_id: auth.user.id,
+                    secret: secret.clone(),
+                })
+                .execute(&mut db_connection)?;
+
+            Ok(Some(TwoFactorEnrollment {
+                secret,
+                otpauth_uri,
+                qr_svg,
+            }))
+        }
+    });
+
+    match enroll.await {
+        Ok(enroll_result) => match enroll_result {
+            Ok(Some(enrollment)) => Response::success().data(enrollment).send(),
+            Ok(None) => Response::already_exists()
+                .msg("Two-factor authentication is already enabled")
+                .send(),
+            Err(e) => {
+                server_error(e);
+                Response::server_error().send()
+            }
+        },
+        Err(e) => {
+            server_error(e);
+            Response::server_error().send()
+        }
+    }
+}
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "enroll2fa",
+    resource: Resource::Auth,
+    method: "POST",
+    path: "/enroll-2fa",
+    summary: "Start enrolling in two-factor authentication",
+    description: "Generates a TOTP secret and returns it with an `otpauth://` URI and a QR code as SVG. Two-factor authentication is only enabled after confirming it with `/confirm-2fa`. Enrolling again before that replaces the secret.",
+    params: &[],
+    query: &[],
+    headers: &super::HEADERS,
+    body: None,
+    body_schema: None,
+    form: false,
+    data_schema: Some(|gen| gen.subschema_for::<TwoFactorEnrollment>()),
+    returns: &[
+        Returns {
+            response_type: ResponseType::Success,
+            description: "with the data `TwoFactorEnrollment`",
+        },
+        Returns {
+            response_type: ResponseType::AlreadyExists,
+            description: "if two-factor authentication is already enabled",
+        },
+        super::AUTH_RETURNS[0],
+        super::AUTH_RETURNS[1],
+    ],
+    example_body: None,
+    example_response: r#"{
+   "type": "Success",
+   "msg": null,
+   "data": {
+     "secret": "JBSWY3DPEHPK3PXPJBSWY3DPEHPK3PXP",
+     "otpauth_uri": "otpauth://totp/KR%20Api:shanto?secret=JBSWY3DPEHPK3PXPJBSWY3DPEHPK3PXP&issuer=KR%20Api",
+     "qr_svg": "<?xml version=\"1.0\" standalone=\"yes\"?><svg ...></svg>"
+   }
+}"#,
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
@@ -0,0 +1,129 @@
+use crate::schema::{password_resets, users};
+use crate::utils::{random_token, send_email};
+use crate::*;
+use chrono::{Duration, Utc};
+
+/// How long a reset token can be used
+const RESET_TOKEN_MINUTES: i64 = 60;
+
+#[derive(Deserialize, Clone, JsonSchema)]
+pub struct ForgotPassword {
+    pub email: String,
+}
+
+/// Send a password reset email
+#[post("/forgot-password")]
+pub async fn route(pool: web::Data<DbPool>, item: web::Json<ForgotPassword>) -> HttpResponse {
+    let email = item.into_inner().email;
+
+    let send_reset = web::block({
+        let mut db_connection = pool.get().unwrap();
+
+        move || {
+            db_connection.transaction(|connection| {
+                let Some(user) = users::table
+                    .filter(users::email.eq(&email))
+                    .first::<User>(connection)
+                    .optional()?
+                else {
+                    return Ok(false);
+                };
+
+                let now = Utc::now().naive_utc();
+
+                // Only the newest token of a user works
+                diesel::delete(
+                    password_resets::table.filter(
+                        password_resets::user_id
+                            .eq(user.id)
+                            .or(password_resets::expires_at.le(now)),
+                    ),
+                )
+                .execute(connection)?;
+
+                let reset = PasswordResetNew {
+                    token: random_token(32),
+                    user_id: user.id,
+                    expires_at: now + Duration::minutes(RESET_TOKEN_MINUTES),
+                };
+
+                diesel::insert_into(password_resets::table)
+                    .values(&reset)
+                    .execute(connection)?;
+
+                let body = format!(
+                    "Hi {},\n\n\
+                     Use this token to reset your password:\n\n\
+                     {}\n\n\
+                     It expires in {} minutes. If you didn't ask for a new password, ignore this email.",
+                    user.name, reset.token, RESET_TOKEN_MINUTES
+                );
+
+                send_email(&email, "Reset your password", &body, connection)?;
+
+                Ok::<bool, diesel::result::Error>(true)
+            })
+        }
+    });
+
+    match send_reset.await {
+        Ok(send_result) => match send_result {
+            Ok(true) => Response::success().msg("The reset email was sent").send(),
+            Ok(false) => Response::not_found().msg("No user has this email").send(),
+            Err(e) => {
+                server_error(e);
+                Response::server_error().send()
+            }
+        },
+        Err(e) => {
+            server_error(e);
+            Response::server_error().send()
+        }
+    }
+}
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "forgotPassword",
+    resource: Resource::Auth,
+    method: "POST",
+    path: "/forgot-password",
+    summary: "Send a password reset email",
+    description: "Puts an email with a single-use reset token in the local mailbox (`/__mailbox`). The token expires in an hour, and asking again replaces the previous one.",
+    params: &[],
+    query: &[],
+    headers: &[],
+    body: Some(
+        r#"{
+    "email": string
+}"#,
+    ),
+    body_schema: Some(|gen| gen.subschema_for::<ForgotPassword>()),
+    form: false,
+    data_schema: None,
+    returns: &[
+        Returns {
+            response_type: ResponseType::Success,
+            description: "if the email was sent",
+        },
+        Returns {
+            response_type: ResponseType::NotFound,
+            description: "if no user has the email",
+        },
+        Returns {
+            response_type: ResponseType::ServerError,
+            description: "if any error occurs",
+        },
+    ],
+    example_body: Some(
+        r#"{
+  "email": "shanto@gmail.com"
+}"#,
+    ),
+    example_response: r#"{
+   "type": "Success",
+   "msg": "The reset email was sent"
+}"#,
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
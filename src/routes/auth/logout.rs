@@ -0,0 +1,72 @@
+use crate::schema::sessions;
+use crate::*;
+
+use super::{AuthUser, SessionOptions};
+
+/// End the session
+#[post("/logout")]
+pub async fn route(
+    pool: Data<DbPool>,
+    options: Data<SessionOptions>,
+    auth: AuthUser,
+) -> HttpResponse {
+    let delete_session = web::block({
+        let mut db_connection = pool.get().unwrap();
+
+        move || {
+            diesel::delete(sessions::table.filter(sessions::id.eq(auth.session.id)))
+                .execute(&mut db_connection)
+        }
+    });
+
+    match delete_session.await {
+        Ok(delete_result) => match delete_result {
+            Ok(_) => {
+                let mut response = Response::success().msg("Logged out").send();
+                options.clear_cookies(&mut response);
+
+                response
+            }
+            Err(e) => {
+                server_error(e);
+                Response::server_error().send()
+            }
+        },
+        Err(e) => {
+            server_error(e);
+            Response::server_error().send()
+        }
+    }
+}
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "logout",
+    resource: Resource::Auth,
+    method: "POST",
+    path: "/logout",
+    summary: "End the session",
+    description: "Deletes the session and removes the session cookies.",
+    params: &[],
+    query: &[],
+    headers: &super::HEADERS,
+    body: None,
+    body_schema: None,
+    form: false,
+    data_schema: None,
+    returns: &[
+        Returns {
+            response_type: ResponseType::Success,
+            description: "if the session was ended",
+        },
+        super::AUTH_RETURNS[0],
+        super::AUTH_RETURNS[1],
+    ],
+    example_body: None,
+    example_response: r#"{
+   "type": "Success",
+   "msg": "Logged out"
+}"#,
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
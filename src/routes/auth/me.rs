@@ -0,0 +1,51 @@
+use crate::*;
+
+use super::AuthUser;
+
+/// Get the logged in user
+#[get("/me")]
+pub async fn route(auth: AuthUser) -> HttpResponse {
+    Response::success().data(UserJson::from(auth.user)).send()
+}
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "me",
+    resource: Resource::Auth,
+    method: "GET",
+    path: "/me",
+    summary: "Get the logged in user",
+    description: "Returns the user of the session, from the bearer token or the session cookie.",
+    params: &[],
+    query: &[],
+    headers: &[super::HEADERS[0]],
+    body: None,
+    body_schema: None,
+    form: false,
+    data_schema: Some(|gen| gen.subschema_for::<UserJson>()),
+    returns: &[
+        Returns {
+            response_type: ResponseType::Success,
+            description: "with the data `UserJson`",
+        },
+        super::AUTH_RETURNS[0],
+        super::AUTH_RETURNS[1],
+    ],
+    example_body: None,
+    example_response: r#"{
+   "type": "Success",
+   "msg": null,
+   "data": {
+     "id": 1,
+     "name": "Shanto",
+     "username": "shanto",
+     "email": "shanto@example.com",
+     "img_url": null,
+     "phone": null,
+     "created_at": "2023-05-21T07:30:48",
+     "email_verified_at": "2023-05-21T07:31:02"
+   }
+}"#,
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
@@ -0,0 +1,629 @@
+pub mod api_key;
+pub mod confirm_2fa;
+pub mod disable_2fa;
+pub mod enroll_2fa;
+pub mod forgot_password;
+pub mod logout;
+pub mod me;
+pub mod resend_verification;
+pub mod reset_password;
+pub mod verify_email;
+
+use crate::schema::{email_verifications, recovery_codes, sessions, two_factor, users};
+use crate::utils::hash::verify_password;
+use crate::utils::{
+    check_totp_code, login_lock, random_token, record_login_failure, send_email, totp, use_api_key,
+    Lockout, LoginLock,
+};
+use crate::*;
+use actix_web::cookie::{time, Cookie, SameSite};
+use actix_web::dev::Payload;
+use actix_web::error::InternalError;
+use actix_web::http::header::{HeaderName, HeaderValue, AUTHORIZATION};
+use actix_web::http::Method;
+use actix_web::FromRequest;
+use chrono::{Duration, Utc};
+use std::error::Error;
+use std::future::Future;
+use std::pin::Pin;
+
+/// The headers every route that needs a logged in user takes
+const HEADERS: [Param; 2] = [
+    Param {
+        name: "Authorization",
+        description: "`Bearer <token>` with the token from `/verify-user`. Not needed when the session is in a cookie",
+        example: "Bearer 4bV1mQ8xT0cZr7LkP2sN9wYe3HjA6uDf",
+    },
+    Param {
+        name: SessionOptions::CSRF_HEADER,
+        description: "The `csrf_token` from `/verify-user`. Only needed when the session is in a cookie",
+        example: "Xo2pLr8QeT5sVb1nKc7MzW4yHg9dFa3U",
+    },
+];
+
+/// Responses every route that needs a logged in user can send
+const AUTH_RETURNS: [Returns; 2] = [
+    Returns {
+        response_type: ResponseType::Unauthorized,
+        description: "if there is no valid session, or the CSRF token is missing or incorrect",
+    },
+    Returns {
+        response_type: ResponseType::ServerError,
+        description: "if any error occurs",
+    },
+];
+
+/// The `SameSite` attribute of the session cookie
+#[derive(clap::ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CookieSameSite {
+    Strict,
+    #[default]
+    Lax,
+    /// Needs a secure cookie, see `--tls`
+    None,
+}
+
+impl From<CookieSameSite> for SameSite {
+    fn from(same_site: CookieSameSite) -> Self {
+        match same_site {
+            CookieSameSite::Strict => SameSite::Strict,
+            CookieSameSite::Lax => SameSite::Lax,
+            CookieSameSite::None => SameSite::None,
+        }
+    }
+}
+
+/// How sessions are kept
+///
+/// `/verify-user` sets two cookies when it is asked to: the session cookie, which is `HttpOnly`,
+/// and `<name>_csrf` with the CSRF token, which the frontend can read.
+#[derive(Clone, Debug)]
+pub struct SessionOptions {
+    /// Name of the session cookie
+    pub cookie_name: String,
+    pub same_site: CookieSameSite,
+    /// Only send the cookies over HTTPS
+    pub secure: bool,
+    /// The `Domain` attribute of the cookies
+    pub domain: Option<String>,
+    /// How long a session lasts
+    pub ttl: Duration,
+}
+
+impl Default for SessionOptions {
+    fn default() -> Self {
+        Self {
+            cookie_name: "krapi_session".to_string(),
+            same_site: CookieSameSite::Lax,
+            secure: false,
+            domain: None,
+            ttl: Duration::days(7),
+        }
+    }
+}
+
+impl SessionOptions {
+    /// The header that carries the CSRF token
+    pub const CSRF_HEADER: &'static str = "X-CSRF-Token";
+
+    fn csrf_cookie_name(&self) -> String {
+        format!("{}_csrf", self.cookie_name)
+    }
+
+    fn cookie<'c>(&self, name: String, value: String, max_age: Duration) -> Cookie<'c> {
+        let mut cookie = Cookie::build(name, value)
+            .path("/")
+            .same_site(self.same_site.into())
+            .secure(self.secure)
+            .max_age(time::Duration::seconds(max_age.num_seconds()))
+            .finish();
+
+        if let Some(domain) = &self.domain {
+            cookie.set_domain(domain.clone());
+        }
+
+        cookie
+    }
+
+    /// Set the session cookie and the CSRF cookie
+    pub fn set_cookies(&self, response: &mut HttpResponse, session: &Session) {
+        let max_age = session.expires_at - Utc::now().naive_utc();
+
+        let mut session_cookie =
+            self.cookie(self.cookie_name.clone(), session.token.clone(), max_age);
+        session_cookie.set_http_only(true);
+        let csrf_cookie = self.cookie(self.csrf_cookie_name(), session.csrf_token.clone(), max_age);
+
+        for cookie in [session_cookie, csrf_cookie] {
+            if let Err(e) = response.add_cookie(&cookie) {
+                server_error(e);
+            }
+        }
+    }
+
+    /// Remove the session cookie and the CSRF cookie
+    pub fn clear_cookies(&self, response: &mut HttpResponse) {
+        for name in [self.cookie_name.clone(), self.csrf_cookie_name()] {
+            let cookie = self.cookie(name, String::new(), Duration::zero());
+            if let Err(e) = response.add_cookie(&cookie) {
+                server_error(e);
+            }
+        }
+    }
+}
+
+/// Who can log in
+#[derive(Clone, Debug, Default)]
+pub struct LoginOptions {
+    /// Reject the users that haven't verified their email
+    pub require_verified_email: bool,
+    /// Lock the account after too many failed logins
+    pub lockout: Lockout,
+}
+
+/// How long the link in the verification email works
+const VERIFICATION_TOKEN_HOURS: i64 = 24;
+
+/// Put an email with a verification link for the user in the local mailbox
+///
+/// `base_url` is where the server is reached, like `http://localhost:8090`.
+/// The earlier links of the user stop working. Does nothing if the user has no email.
+pub fn send_verification_email(
+    user: &User,
+    base_url: &str,
+    connection: &mut SqliteConnection,
+) -> QueryResult<()> {
+    let Some(email) = &user.email else {
+        return Ok(());
+    };
+
+    diesel::delete(email_verifications::table.filter(email_verifications::user_id.eq(user.id)))
+        .execute(connection)?;
+
+    let verification = EmailVerificationNew {
+        token: random_token(32),
+        user_id: user.id,
+        expires_at: Utc::now().naive_utc() + Duration::hours(VERIFICATION_TOKEN_HOURS),
+    };
+
+    diesel::insert_into(email_verifications::table)
+        .values(&verification)
+        .execute(connection)?;
+
+    let body = format!(
+        "Hi {},\n\n\
+         Open this link to verify your email:\n\n\
+         {}/verify-email?token={}\n\n\
+         It expires in {} hours.",
+        user.name, base_url, verification.token, VERIFICATION_TOKEN_HOURS
+    );
+
+    send_email(email, "Verify your email", &body, connection)
+}
+
+/// How the second factor of a user checked out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecondFactor {
+    /// The user hasn't enabled two-factor authentication
+    NotEnabled,
+    /// It is enabled, but no code was given
+    Missing,
+    Incorrect,
+    Correct,
+}
+
+/// Check a code from the authenticator app, or a recovery code
+///
+/// A recovery code can only be used once, so it is deleted when it matches.
+pub fn check_second_factor(
+    user: &User,
+    code: Option<&str>,
+    connection: &mut SqliteConnection,
+) -> Result<SecondFactor, Box<dyn Error + Send + Sync + 'static>> {
+    let Some(two_factor) = two_factor::table
+        .filter(two_factor::user_id.eq(user.id))
+        .filter(two_factor::enabled_at.is_not_null())
+        .first::<TwoFactor>(connection)
+        .optional()?
+    else {
+        return Ok(SecondFactor::NotEnabled);
+    };
+
+    let Some(code) = code.map(str::trim).filter(|code| !code.is_empty()) else {
+        return Ok(SecondFactor::Missing);
+    };
+
+    if check_totp_code(&totp(&two_factor.secret, &user.username)?, code) {
+        return Ok(SecondFactor::Correct);
+    }
+
+    let recovery_codes = recovery_codes::table
+        .filter(recovery_codes::user_id.eq(user.id))
+        .load::<RecoveryCode>(connection)?;
+
+    for recovery_code in recovery_codes {
+        if verify_password(code.to_lowercase(), &recovery_code.code_hash) {
+            diesel::delete(recovery_codes::table.filter(recovery_codes::id.eq(recovery_code.id)))
+                .execute(connection)?;
+
+            return Ok(SecondFactor::Correct);
+        }
+    }
+
+    Ok(SecondFactor::Incorrect)
+}
+
+/// The IP that failed logins are counted for
+///
+/// Not `realip_remote_addr`, which trusts the `X-Forwarded-For` header of the client.
+pub fn client_ip(req: &HttpRequest) -> String {
+    req.peer_addr()
+        .map(|addr| addr.ip().to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Check if too many logins of the user, or from the IP, failed
+///
+/// Returns the response to send if the login is locked.
+pub async fn check_login_lock(
+    pool: &DbPool,
+    user_id: Option<i32>,
+    ip: &str,
+    lockout: &Lockout,
+) -> Result<(), HttpResponse> {
+    let lock = web::block({
+        let ip = ip.to_string();
+        let lockout = lockout.clone();
+        let mut db_connection = pool.get().unwrap();
+
+        move || login_lock(user_id, &ip, &lockout, &mut db_connection)
+    });
+
+    match lock.await {
+        Ok(lock_result) => match lock_result {
+            Ok(LoginLock::Open(_)) => Ok(()),
+            Ok(LoginLock::Locked(lockout)) => Err(locked(lockout)),
+            Err(e) => {
+                server_error(e);
+                Err(Response::server_error().send())
+            }
+        },
+        Err(e) => {
+            server_error(e);
+            Err(Response::server_error().send())
+        }
+    }
+}
+
+/// Record a failed login and send the response with the number of failed attempts
+///
+/// If this failure locked the account, [`ResponseType::AccountLocked`] is sent instead.
+pub async fn login_failed(
+    pool: &DbPool,
+    user_id: Option<i32>,
+    ip: &str,
+    lockout: &Lockout,
+    response: Response,
+) -> HttpResponse {
+    let lock = web::block({
+        let ip = ip.to_string();
+        let lockout = lockout.clone();
+        let mut db_connection = pool.get().unwrap();
+
+        move || {
+            record_login_failure(user_id, &ip, &mut db_connection)?;
+            login_lock(user_id, &ip, &lockout, &mut db_connection)
+        }
+    });
+
+    match lock.await {
+        Ok(lock_result) => match lock_result {
+            Ok(LoginLock::Open(attempts)) => response.data(attempts).send(),
+            Ok(LoginLock::Locked(lockout)) => locked(lockout),
+            Err(e) => {
+                server_error(e);
+                Response::server_error().send()
+            }
+        },
+        Err(e) => {
+            server_error(e);
+            Response::server_error().send()
+        }
+    }
+}
+
+/// The response to a login while the account is locked, with the `Retry-After` header
+pub fn locked(lockout: LockoutJson) -> HttpResponse {
+    let mut response = Response::account_locked()
+        .msg(&format!(
+            "Too many failed logins. Try again in {} seconds",
+            lockout.retry_after
+        ))
+        .data(lockout)
+        .send();
+
+    response.headers_mut().insert(
+        HeaderName::from_static("retry-after"),
+        HeaderValue::from(lockout.retry_after),
+    );
+
+    response
+}
+
+/// Create a session for the user
+///
+/// The expired sessions of every user are deleted at the same time.
+pub fn start_session(
+    user_id: i32,
+    ttl: Duration,
+    connection: &mut SqliteConnection,
+) -> QueryResult<Session> {
+    let now = Utc::now().naive_utc();
+
+    diesel::delete(sessions::table.filter(sessions::expires_at.le(now))).execute(connection)?;
+
+    let session = SessionNew {
+        token: random_token(32),
+        csrf_token: random_token(32),
+        user_id,
+        expires_at: now + ttl,
+    };
+
+    diesel::insert_into(sessions::table)
+        .values(&session)
+        .execute(connection)?;
+
+    sessions::table
+        .filter(sessions::token.eq(&session.token))
+        .first(connection)
+}
+
+/// The logged in user of a request
+///
+/// The session is read from the `Authorization: Bearer <token>` header, or else from the session cookie.
+/// When it comes from the cookie, every request except `GET`, `HEAD` and `OPTIONS` also needs
+/// the CSRF token in the `X-CSRF-Token` header.
+///
+/// If there is no valid session, the route is not called and [`ResponseType::Unauthorized`] is sent instead.
+pub struct AuthUser {
+    pub user: User,
+    pub session: Session,
+}
+
+impl FromRequest for AuthUser {
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let req = req.clone();
+
+        Box::pin(async move {
+            let reject = |response: Response| -> actix_web::Error {
+                InternalError::from_response("", response.send()).into()
+            };
+
+            let Some(pool) = req.app_data::<Data<DbPool>>() else {
+                return Err(reject(Response::server_error()));
+            };
+            let options = req
+                .app_data::<Data<SessionOptions>>()
+                .map(|options| options.get_ref().clone())
+                .unwrap_or_default();
+
+            let bearer = req
+                .headers()
+                .get(AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "))
+                .map(|token| token.trim().to_string());
+
+            let (token, from_cookie) = match bearer {
+                Some(token) => (token, false),
+                None => match req.cookie(&options.cookie_name) {
+                    Some(cookie) => (cookie.value().to_string(), true),
+                    None => {
+                        return Err(reject(Response::unauthorized().msg("You need to log in")));
+                    }
+                },
+            };
+
+            let session = web::block({
+                let mut db_connection = pool.get().unwrap();
+
+                move || {
+                    sessions::table
+                        .inner_join(users::table)
+                        .filter(sessions::token.eq(&token))
+                        .filter(sessions::expires_at.gt(Utc::now().naive_utc()))
+                        .first::<(Session, User)>(&mut db_connection)
+                        .optional()
+                }
+            });
+
+            let (session, user) = match session.await {
+                Ok(session_result) => match session_result {
+                    Ok(Some(session)) => session,
+                    Ok(None) => {
+                        return Err(reject(
+                            Response::unauthorized().msg("The session is invalid or expired"),
+                        ));
+                    }
+                    Err(e) => {
+                        server_error(e);
+                        return Err(reject(Response::server_error()));
+                    }
+                },
+                Err(e) => {
+                    server_error(e);
+                    return Err(reject(Response::server_error()));
+                }
+            };
+
+            // Other sites can make the browser send the cookie, but they can't read the CSRF token
+            let is_safe = matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+            if from_cookie && !is_safe {
+                let csrf_token = req
+                    .headers()
+                    .get(SessionOptions::CSRF_HEADER)
+                    .and_then(|value| value.to_str().ok());
+
+                if csrf_token != Some(session.csrf_token.as_str()) {
+                    return Err(reject(
+                        Response::unauthorized().msg("Missing or incorrect CSRF token"),
+                    ));
+                }
+            }
+
+            Ok(AuthUser { user, session })
+        })
+    }
+}
+
+/// A machine client, like a backend, that sends an API key instead of logging in as a user
+///
+/// The key is read from the `X-API-Key` header. Keys are made with `krapi keys create`.
+///
+/// If the key is missing, invalid or expired, the route is not called and [`ResponseType::Unauthorized`] is sent instead.
+pub struct ApiClient {
+    pub key: ApiKey,
+}
+
+impl ApiClient {
+    /// The header that carries the API key
+    pub const HEADER: &'static str = "X-API-Key";
+
+    /// Check that the key has the scope
+    ///
+    /// If it doesn't, returns the response that should be sent instead.
+    pub fn require_scope(&self, scope: &str) -> Result<(), HttpResponse> {
+        if !self.key.has_scope(scope) {
+            return Err(Response::no_permission()
+                .msg(&format!("The API key needs the {} scope", scope))
+                .send());
+        }
+
+        Ok(())
+    }
+}
+
+impl FromRequest for ApiClient {
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let req = req.clone();
+
+        Box::pin(async move {
+            let reject = |response: Response| -> actix_web::Error {
+                InternalError::from_response("", response.send()).into()
+            };
+
+            let Some(pool) = req.app_data::<Data<DbPool>>() else {
+                return Err(reject(Response::server_error()));
+            };
+
+            let Some(key) = req
+                .headers()
+                .get(Self::HEADER)
+                .and_then(|value| value.to_str().ok())
+                .map(|key| key.trim().to_string())
+            else {
+                return Err(reject(
+                    Response::unauthorized().msg("Send an API key in the X-API-Key header"),
+                ));
+            };
+
+            let api_key = web::block({
+                let mut db_connection = pool.get().unwrap();
+
+                move || use_api_key(&key, &mut db_connection)
+            });
+
+            match api_key.await {
+                Ok(api_key_result) => match api_key_result {
+                    Ok(Some(key)) => Ok(ApiClient { key }),
+                    Ok(None) => Err(reject(
+                        Response::unauthorized().msg("The API key is invalid or expired"),
+                    )),
+                    Err(e) => {
+                        server_error(e);
+                        Err(reject(Response::server_error()))
+                    }
+                },
+                Err(e) => {
+                    server_error(e);
+                    Err(reject(Response::server_error()))
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hash::hash_password;
+    use crate::utils::{generate_recovery_codes, generate_totp_secret, test_connection};
+
+    /// A user with two-factor authentication and its recovery codes
+    fn user_with_two_factor(connection: &mut SqliteConnection) -> (User, Vec<String>) {
+        diesel::insert_into(users::table)
+            .values(&UserNew {
+                name: "Shanto".to_string(),
+                username: "shanto".to_string(),
+                email: None,
+                img_url: None,
+                phone: None,
+                password: String::new(),
+            })
+            .execute(connection)
+            .unwrap();
+        let user = users::table.first::<User>(connection).unwrap();
+
+        diesel::insert_into(two_factor::table)
+            .values(&TwoFactorNew {
+                user_id: user.id,
+                secret: generate_totp_secret(),
+            })
+            .execute(connection)
+            .unwrap();
+        diesel::update(two_factor::table.find(user.id))
+            .set(two_factor::enabled_at.eq(Utc::now().naive_utc()))
+            .execute(connection)
+            .unwrap();
+
+        let codes = generate_recovery_codes()[..2].to_vec();
+        for code in &codes {
+            diesel::insert_into(recovery_codes::table)
+                .values(&RecoveryCodeNew {
+                    user_id: user.id,
+                    code_hash: hash_password(code),
+                })
+                .execute(connection)
+                .unwrap();
+        }
+
+        (user, codes)
+    }
+
+    #[test]
+    fn recovery_codes_work_once() {
+        let connection = &mut test_connection();
+        let (user, codes) = user_with_two_factor(connection);
+
+        let check = |code, connection: &mut SqliteConnection| {
+            check_second_factor(&user, code, connection).unwrap()
+        };
+
+        assert_eq!(check(None, connection), SecondFactor::Missing);
+        assert_eq!(check(Some(&codes[0]), connection), SecondFactor::Correct);
+        assert_eq!(check(Some(&codes[0]), connection), SecondFactor::Incorrect);
+
+        // The other codes still work, and the case doesn't matter
+        assert_eq!(
+            check(Some(&codes[1].to_uppercase()), connection),
+            SecondFactor::Correct
+        );
+    }
+}
@@ -0,0 +1,126 @@
+use crate::schema::users;
+use crate::*;
+
+use super::send_verification_email;
+
+/// How sending the verification email again went
+enum Resend {
+    Sent,
+    NotFound,
+    AlreadyVerified,
+}
+
+#[derive(Deserialize, Clone, JsonSchema)]
+pub struct ResendVerification {
+    pub email: String,
+}
+
+/// Send the verification email again
+#[post("/resend-verification-email")]
+pub async fn route(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    item: web::Json<ResendVerification>,
+) -> HttpResponse {
+    let email = item.into_inner().email;
+
+    // Where the link in the verification email points to
+    let base_url = {
+        let connection_info = req.connection_info();
+        format!("{}://{}", connection_info.scheme(), connection_info.host())
+    };
+
+    let resend = web::block({
+        let mut db_connection = pool.get().unwrap();
+
+        move || {
+            db_connection.transaction(|connection| {
+                let Some(user) = users::table
+                    .filter(users::email.eq(&email))
+                    .first::<User>(connection)
+                    .optional()?
+                else {
+                    return Ok(Resend::NotFound);
+                };
+
+                if user.email_verified_at.is_some() {
+                    return Ok(Resend::AlreadyVerified);
+                }
+
+                send_verification_email(&user, &base_url, connection)?;
+
+                Ok::<Resend, diesel::result::Error>(Resend::Sent)
+            })
+        }
+    });
+
+    match resend.await {
+        Ok(resend_result) => match resend_result {
+            Ok(Resend::Sent) => Response::success()
+                .msg("The verification email was sent")
+                .send(),
+            Ok(Resend::NotFound) => Response::not_found().msg("No user has this email").send(),
+            Ok(Resend::AlreadyVerified) => Response::invalid_input()
+                .msg("The email is already verified")
+                .send(),
+            Err(e) => {
+                server_error(e);
+                Response::server_error().send()
+            }
+        },
+        Err(e) => {
+            server_error(e);
+            Response::server_error().send()
+        }
+    }
+}
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "resendVerificationEmail",
+    resource: Resource::Auth,
+    method: "POST",
+    path: "/resend-verification-email",
+    summary: "Send the verification email again",
+    description: "Puts a new verification email in the local mailbox (`/__mailbox`). The earlier links of the user stop working.",
+    params: &[],
+    query: &[],
+    headers: &[],
+    body: Some(
+        r#"{
+    "email": string
+}"#,
+    ),
+    body_schema: Some(|gen| gen.subschema_for::<ResendVerification>()),
+    form: false,
+    data_schema: None,
+    returns: &[
+        Returns {
+            response_type: ResponseType::Success,
+            description: "if the email was sent",
+        },
+        Returns {
+            response_type: ResponseType::NotFound,
+            description: "if no user has the email",
+        },
+        Returns {
+            response_type: ResponseType::InvalidInput,
+            description: "if the email is already verified",
+        },
+        Returns {
+            response_type: ResponseType::ServerError,
+            description: "if any error occurs",
+        },
+    ],
+    example_body: Some(
+        r#"{
+  "email": "shanto@gmail.com"
+}"#,
+    ),
+    example_response: r#"{
+   "type": "Success",
+   "msg": "The verification email was sent"
+}"#,
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
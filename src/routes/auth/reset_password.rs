@@ -0,0 +1,119 @@
+use crate::schema::{password_resets, sessions, users};
+use crate::utils::clear_login_failures;
+use crate::utils::hash::hash_password;
+use crate::*;
+use chrono::Utc;
+
+#[derive(Deserialize, Clone, JsonSchema)]
+pub struct ResetPassword {
+    /// The token from the reset email
+    pub token: String,
+    pub new_password: String,
+}
+
+/// Set a new password with a reset token
+#[post("/reset-password")]
+pub async fn route(pool: web::Data<DbPool>, item: web::Json<ResetPassword>) -> HttpResponse {
+    let reset_info = item.into_inner();
+
+    let reset_password = web::block({
+        let mut db_connection = pool.get().unwrap();
+
+        move || {
+            db_connection.transaction(|connection| {
+                let Some(reset) = password_resets::table
+                    .filter(password_resets::token.eq(&reset_info.token))
+                    .filter(password_resets::expires_at.gt(Utc::now().naive_utc()))
+                    .first::<PasswordReset>(connection)
+                    .optional()?
+                else {
+                    return Ok(false);
+                };
+
+                let hash = hash_password(&reset_info.new_password);
+
+                diesel::update(users::table.filter(users::id.eq(reset.user_id)))
+                    .set(users::password.eq(&hash))
+                    .execute(connection)?;
+
+                // The token is used up, and whoever knew the old password is logged out
+                diesel::delete(
+                    password_resets::table.filter(password_resets::user_id.eq(reset.user_id)),
+                )
+                .execute(connection)?;
+                diesel::delete(sessions::table.filter(sessions::user_id.eq(reset.user_id)))
+                    .execute(connection)?;
+                clear_login_failures(reset.user_id, connection)?;
+
+                Ok::<bool, diesel::result::Error>(true)
+            })
+        }
+    });
+
+    match reset_password.await {
+        Ok(reset_result) => match reset_result {
+            Ok(true) => Response::success()
+                .msg("Password reset successfully")
+                .send(),
+            Ok(false) => Response::invalid_input()
+                .msg("The reset token is invalid or expired")
+                .send(),
+            Err(e) => {
+                server_error(e);
+                Response::server_error().send()
+            }
+        },
+        Err(e) => {
+            server_error(e);
+            Response::server_error().send()
+        }
+    }
+}
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "resetPassword",
+    resource: Resource::Auth,
+    method: "POST",
+    path: "/reset-password",
+    summary: "Set a new password with a reset token",
+    description: "Uses up the token from `/forgot-password`, sets the new password, ends every session of the user and unlocks the account.",
+    params: &[],
+    query: &[],
+    headers: &[],
+    body: Some(
+        r#"{
+    "token": string,
+    "new_password": string
+}"#,
+    ),
+    body_schema: Some(|gen| gen.subschema_for::<ResetPassword>()),
+    form: false,
+    data_schema: None,
+    returns: &[
+        Returns {
+            response_type: ResponseType::Success,
+            description: "if the password was reset",
+        },
+        Returns {
+            response_type: ResponseType::InvalidInput,
+            description: "if the token is invalid, already used or expired",
+        },
+        Returns {
+            response_type: ResponseType::ServerError,
+            description: "if any error occurs",
+        },
+    ],
+    example_body: Some(
+        r#"{
+  "token": "q3Vx9LmT2rB7kPz1Ws8nYc4HdJ6fGa0E",
+  "new_password": "admin006"
+}"#,
+    ),
+    example_response: r#"{
+   "type": "Success",
+   "msg": "Password reset successfully"
+}"#,
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
@@ -0,0 +1,104 @@
+use crate::schema::{email_verifications, users};
+use crate::*;
+use chrono::Utc;
+
+#[derive(Deserialize, Clone, JsonSchema)]
+pub struct VerifyEmail {
+    pub token: String,
+}
+
+/// Verify the user's email
+#[get("/verify-email")]
+pub async fn route(pool: web::Data<DbPool>, query: web::Query<VerifyEmail>) -> HttpResponse {
+    let token = query.into_inner().token;
+
+    let verify_email = web::block({
+        let mut db_connection = pool.get().unwrap();
+
+        move || {
+            db_connection.transaction(|connection| {
+                let now = Utc::now().naive_utc();
+
+                let Some(verification) = email_verifications::table
+                    .filter(email_verifications::token.eq(&token))
+                    .filter(email_verifications::expires_at.gt(now))
+                    .first::<EmailVerification>(connection)
+                    .optional()?
+                else {
+                    return Ok(false);
+                };
+
+                diesel::update(users::table.filter(users::id.eq(verification.user_id)))
+                    .set(users::email_verified_at.eq(now))
+                    .execute(connection)?;
+
+                diesel::delete(
+                    email_verifications::table
+                        .filter(email_verifications::user_id.eq(verification.user_id)),
+                )
+                .execute(connection)?;
+
+                Ok::<bool, diesel::result::Error>(true)
+            })
+        }
+    });
+
+    match verify_email.await {
+        Ok(verify_result) => match verify_result {
+            Ok(true) => Response::success().msg("Email verified").send(),
+            Ok(false) => Response::invalid_input()
+                .msg("The verification link is invalid or expired")
+                .send(),
+            Err(e) => {
+                server_error(e);
+                Response::server_error().send()
+            }
+        },
+        Err(e) => {
+            server_error(e);
+            Response::server_error().send()
+        }
+    }
+}
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "verifyEmail",
+    resource: Resource::Auth,
+    method: "GET",
+    path: "/verify-email",
+    summary: "Verify the user's email",
+    description: "The link in the verification email sent by `/create-user`, `/update-user` when the email changes, or `/resend-verification-email`. Uses up the token and marks the email as verified.",
+    params: &[],
+    query: &[Param {
+        name: "token",
+        description: "The token from the verification email",
+        example: "Hk4sWq9ZpL2xVn7RbT1mYc8dJf3Ga6Ue",
+    }],
+    headers: &[],
+    body: None,
+    body_schema: None,
+    form: false,
+    data_schema: None,
+    returns: &[
+        Returns {
+            response_type: ResponseType::Success,
+            description: "if the email was verified",
+        },
+        Returns {
+            response_type: ResponseType::InvalidInput,
+            description: "if the token is invalid, already used or expired",
+        },
+        Returns {
+            response_type: ResponseType::ServerError,
+            description: "if any error occurs",
+        },
+    ],
+    example_body: None,
+    example_response: r#"{
+   "type": "Success",
+   "msg": "Email verified"
+}"#,
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
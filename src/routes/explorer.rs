@@ -0,0 +1,34 @@
+use crate::*;
+
+/// The explorer page. It is embedded in the binary, so it works offline.
+const EXPLORER_HTML: &str = include_str!("explorer.html");
+
+/// Show the interactive API explorer
+#[get("/docs")]
+pub async fn route() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(EXPLORER_HTML)
+}
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "explorer",
+    resource: Resource::Docs,
+    method: "GET",
+    path: "/docs",
+    summary: "Show the interactive API explorer",
+    description: "An HTML page that loads /openapi.json and lets you try every route. Open it in the browser.",
+    params: &[],
+    query: &[],
+    headers: &[],
+    body: None,
+    body_schema: None,
+    form: false,
+    data_schema: None,
+    returns: &[],
+    example_body: None,
+    example_response: "<!DOCTYPE html>...",
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
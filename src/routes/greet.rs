@@ -1,13 +1,29 @@
 use crate::*;
 
 /// Show a greeting message
-///
-/// This route is used to confirm that the server is running successfully.
-///
-/// ## Route
-///
-/// `GET` localhost:8090/greet
 #[get("/greet")]
 pub async fn route() -> HttpResponse {
     HttpResponse::Ok().body("Hello, world! Welcome to KR Api!")
 }
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "greet",
+    resource: Resource::Greet,
+    method: "GET",
+    path: "/greet",
+    summary: "Show a greeting message",
+    description: "Used to confirm that the server is running. Responds with plain text.",
+    params: &[],
+    query: &[],
+    headers: &[],
+    body: None,
+    body_schema: None,
+    form: false,
+    data_schema: None,
+    returns: &[],
+    example_body: None,
+    example_response: "Hello, world! Welcome to KR Api!",
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
@@ -0,0 +1,93 @@
+use crate::*;
+
+// The files of the docs site, generated by `build.rs`
+include!(concat!(env!("OUT_DIR"), "/guide.rs"));
+
+/// Show the docs site
+#[get("/guide{path:.*}")]
+pub async fn route(path: Path<String>) -> HttpResponse {
+    let path = path.into_inner();
+
+    if GUIDE_FILES.is_empty() {
+        return HttpResponse::NotFound().body(
+            "The docs site is not bundled into this build. Build in release mode, or with KRAPI_BUILD_GUIDE=1, to include it.",
+        );
+    }
+
+    // `/guide` has to end with a slash, so the relative links of the site work
+    if path.is_empty() {
+        return HttpResponse::PermanentRedirect()
+            .insert_header(("Location", "/guide/"))
+            .finish();
+    }
+
+    let Some(path) = path.strip_prefix('/') else {
+        return HttpResponse::NotFound().finish();
+    };
+
+    // The site is exported with trailing slashes, so pages are `<page>/index.html`
+    let candidates = [
+        path.to_string(),
+        format!("{}index.html", path),
+        format!("{}/index.html", path),
+        format!("{}.html", path),
+    ];
+
+    let file = candidates.iter().find_map(|candidate| {
+        GUIDE_FILES
+            .iter()
+            .find(|(name, _)| name == candidate)
+            .map(|(name, content)| (*name, *content))
+    });
+
+    match file {
+        Some((name, content)) => HttpResponse::Ok()
+            .content_type(content_type(name))
+            .body(content),
+        None => match GUIDE_FILES.iter().find(|(name, _)| *name == "404.html") {
+            Some((_, content)) => HttpResponse::NotFound()
+                .content_type("text/html; charset=utf-8")
+                .body(*content),
+            None => HttpResponse::NotFound().finish(),
+        },
+    }
+}
+
+fn content_type(name: &str) -> &'static str {
+    match name.rsplit('.').next().unwrap_or_default() {
+        "html" => "text/html; charset=utf-8",
+        "js" => "text/javascript; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "json" => "application/json",
+        "txt" => "text/plain; charset=utf-8",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        _ => "application/octet-stream",
+    }
+}
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "guide",
+    resource: Resource::Docs,
+    method: "GET",
+    path: "/guide",
+    summary: "Show the docs site",
+    description: "The docs site, embedded in release builds. Open it in the browser.",
+    params: &[],
+    query: &[],
+    headers: &[],
+    body: None,
+    body_schema: None,
+    form: false,
+    data_schema: None,
+    returns: &[],
+    example_body: None,
+    example_response: "<!DOCTYPE html>...",
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
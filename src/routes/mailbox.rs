@@ -0,0 +1,72 @@
+use crate::schema::emails;
+use crate::*;
+
+/// Read the local mailbox
+#[get("/__mailbox")]
+pub async fn route(pool: web::Data<DbPool>) -> HttpResponse {
+    let mut db_connection = pool.get().unwrap();
+
+    let result = web::block(move || {
+        emails::table
+            .order(emails::id.desc())
+            .load::<Email>(&mut db_connection)
+    });
+
+    match result.await {
+        Ok(emails_result) => match emails_result {
+            Ok(emails) => Response::success().data(emails).send(),
+            Err(e) => {
+                server_error(e);
+                Response::server_error().send()
+            }
+        },
+        Err(e) => {
+            server_error(e);
+            Response::server_error().send()
+        }
+    }
+}
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "mailbox",
+    resource: Resource::Mailbox,
+    method: "GET",
+    path: "/__mailbox",
+    summary: "Read the local mailbox",
+    description:
+        "Returns the emails the server has sent, newest first. No email leaves the machine.",
+    params: &[],
+    query: &[],
+    headers: &[],
+    body: None,
+    body_schema: None,
+    form: false,
+    data_schema: Some(|gen| gen.subschema_for::<Vec<Email>>()),
+    returns: &[
+        Returns {
+            response_type: ResponseType::Success,
+            description: "with the data `Email[]`",
+        },
+        Returns {
+            response_type: ResponseType::ServerError,
+            description: "if any error occurs",
+        },
+    ],
+    example_body: None,
+    example_response: r#"{
+   "type": "Success",
+   "msg": null,
+   "data": [
+     {
+       "id": 1,
+       "recipient": "shanto@gmail.com",
+       "subject": "Reset your password",
+       "body": "Hi Shanto Islam,\n\nUse this token to reset your password: ...",
+       "created_at": "2023-05-21T07:30:48"
+     }
+   ]
+}"#,
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
@@ -1,4 +1,12 @@
+mod admin;
+pub mod auth;
+mod explorer;
 mod greet;
+mod guide;
+mod mailbox;
+mod oidc;
+mod openapi;
+pub mod registry;
 mod user;
 
 pub use user::create_user::route as create_user_route;
@@ -8,4 +16,35 @@ pub use user::match_user::route as match_user_route;
 pub use user::update_password::route as update_password_route;
 pub use user::update_user::route as update_user_route;
 
+pub use auth::api_key::route as api_key_route;
+pub use auth::confirm_2fa::route as confirm_2fa_route;
+pub use auth::disable_2fa::route as disable_2fa_route;
+pub use auth::enroll_2fa::route as enroll_2fa_route;
+pub use auth::forgot_password::route as forgot_password_route;
+pub use auth::logout::route as logout_route;
+pub use auth::me::route as me_route;
+pub use auth::resend_verification::route as resend_verification_route;
+pub use auth::reset_password::route as reset_password_route;
+pub use auth::verify_email::route as verify_email_route;
+pub use auth::{ApiClient, AuthUser, LoginOptions, SessionOptions};
+
+pub use explorer::route as explorer_route;
 pub use greet::route as greet_route;
+pub use guide::route as guide_route;
+pub use mailbox::route as mailbox_route;
+
+pub use oidc::authorize::route as oidc_authorize_route;
+pub use oidc::configuration::route as oidc_configuration_route;
+pub use oidc::grant::route as oidc_grant_route;
+pub use oidc::jwks::route as oidc_jwks_route;
+pub use oidc::token::route as oidc_token_route;
+pub use oidc::userinfo::route as oidc_userinfo_route;
+pub use openapi::route as openapi_route;
+
+pub use admin::list_snapshots::route as admin_list_snapshots_route;
+pub use admin::reset::route as admin_reset_route;
+pub use admin::restore_snapshot::route as admin_restore_snapshot_route;
+pub use admin::save_snapshot::route as admin_save_snapshot_route;
+pub use admin::AdminToken;
+
+pub use registry::{configure as configure_routes, ROUTES};
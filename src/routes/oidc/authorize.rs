@@ -0,0 +1,161 @@
+use crate::schema::users;
+use crate::*;
+
+use super::{escape_html, page, AuthorizeParams};
+
+/// Show the login page of the OpenID Connect provider
+#[get("/authorize")]
+pub async fn route(pool: web::Data<DbPool>, query: web::Query<AuthorizeParams>) -> HttpResponse {
+    let params = query.into_inner();
+
+    if let Err(e) = params.validate() {
+        return e.respond(&params);
+    }
+
+    let mut db_connection = pool.get().unwrap();
+
+    let result = web::block(move || {
+        users::table
+            .order(users::username.asc())
+            .load::<User>(&mut db_connection)
+    });
+
+    let users = match result.await {
+        Ok(users_result) => match users_result {
+            Ok(users) => users,
+            Err(e) => {
+                server_error(e);
+                return HttpResponse::InternalServerError().finish();
+            }
+        },
+        Err(e) => {
+            server_error(e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(picker_page(&params, &users))
+}
+
+/// A form with a button for every user, that posts the request back to `/authorize`
+fn picker_page(params: &AuthorizeParams, users: &[User]) -> String {
+    let client_id = params.client_id.as_deref().unwrap_or_default();
+
+    let hidden = serde_json::to_value(params)
+        .ok()
+        .and_then(|value| value.as_object().cloned())
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|(name, value)| {
+            value.as_str().map(|value| {
+                format!(
+                    r#"<input type="hidden" name="{}" value="{}">"#,
+                    escape_html(&name),
+                    escape_html(value)
+                )
+            })
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let buttons = users
+        .iter()
+        .map(|user| {
+            let email = user
+                .email
+                .as_deref()
+                .map(|email| format!(" &middot; {}", escape_html(email)))
+                .unwrap_or_default();
+
+            format!(
+                r#"<button type="submit" name="user_id" value="{}">{}<small>@{}{}</small></button>"#,
+                user.id,
+                escape_html(&user.name),
+                escape_html(&user.username),
+                email
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let users = if users.is_empty() {
+        "<p>There are no users yet. Add some with <code>krapi seed</code> or <code>krapi generate</code>.</p>"
+            .to_string()
+    } else {
+        buttons
+    };
+
+    let content = format!(
+        r#"<p>Pick a user to log in to <strong>{}</strong> with.</p>
+<form method="post" action="/authorize">
+{}
+{}
+<button type="submit" class="cancel">Cancel</button>
+</form>"#,
+        escape_html(client_id),
+        hidden,
+        users
+    );
+
+    page("Log in", &content)
+}
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "authorize",
+    resource: Resource::Oidc,
+    method: "GET",
+    path: "/authorize",
+    summary: "Show the login page of the OpenID Connect provider",
+    description: "An HTML page that lists the users. Picking one redirects back to the `redirect_uri` with an authorization code. Any client id and redirect URI are accepted. The provider is only served with `--oidc`.",
+    params: &[],
+    query: &[
+        Param {
+            name: "response_type",
+            description: "Only `code`",
+            example: "code",
+        },
+        Param {
+            name: "client_id",
+            description: "Any name of the client",
+            example: "my-spa",
+        },
+        Param {
+            name: "redirect_uri",
+            description: "Where to send the browser back to",
+            example: "http://localhost:3000/callback",
+        },
+        Param {
+            name: "scope",
+            description: "Space separated, like `openid profile email`",
+            example: "openid",
+        },
+        Param {
+            name: "state",
+            description: "Sent back as it is",
+            example: "af0ifjsldkj",
+        },
+        Param {
+            name: "code_challenge",
+            description: "The PKCE challenge",
+            example: "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM",
+        },
+        Param {
+            name: "code_challenge_method",
+            description: "`S256` or `plain`",
+            example: "S256",
+        },
+    ],
+    headers: &[],
+    body: None,
+    body_schema: None,
+    form: false,
+    data_schema: None,
+    returns: &[],
+    example_body: None,
+    example_response: "<!DOCTYPE html>...",
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
@@ -0,0 +1,80 @@
+use crate::*;
+
+use super::{issuer, SCOPES};
+
+/// Get the OpenID Connect discovery document
+#[get("/.well-known/openid-configuration")]
+pub async fn route(req: HttpRequest) -> HttpResponse {
+    let issuer = issuer(&req);
+    let list = |items: &[&str]| items.iter().map(|item| item.to_string()).collect();
+
+    HttpResponse::Ok().json(OpenIdConfiguration {
+        authorization_endpoint: format!("{}/authorize", issuer),
+        token_endpoint: format!("{}/token", issuer),
+        userinfo_endpoint: format!("{}/userinfo", issuer),
+        jwks_uri: format!("{}/jwks.json", issuer),
+        issuer,
+        response_types_supported: list(&["code"]),
+        response_modes_supported: list(&["query"]),
+        grant_types_supported: list(&["authorization_code"]),
+        subject_types_supported: list(&["public"]),
+        id_token_signing_alg_values_supported: list(&["ES256"]),
+        scopes_supported: list(&SCOPES),
+        token_endpoint_auth_methods_supported: list(&[
+            "none",
+            "client_secret_basic",
+            "client_secret_post",
+        ]),
+        code_challenge_methods_supported: list(&["S256", "plain"]),
+        claims_supported: list(&[
+            "sub",
+            "iss",
+            "aud",
+            "exp",
+            "iat",
+            "auth_time",
+            "nonce",
+            "name",
+            "preferred_username",
+            "email",
+            "email_verified",
+        ]),
+    })
+}
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "openidConfiguration",
+    resource: Resource::Oidc,
+    method: "GET",
+    path: "/.well-known/openid-configuration",
+    summary: "Get the OpenID Connect discovery document",
+    description: "Lists the endpoints of the built-in OpenID Connect provider. Use the server itself as the authority of OIDC client libraries. Not wrapped in the usual response object.",
+    params: &[],
+    query: &[],
+    headers: &[],
+    body: None,
+    body_schema: None,
+    form: false,
+    data_schema: None,
+    returns: &[],
+    example_body: None,
+    example_response: r#"{
+  "issuer": "http://localhost:8090",
+  "authorization_endpoint": "http://localhost:8090/authorize",
+  "token_endpoint": "http://localhost:8090/token",
+  "userinfo_endpoint": "http://localhost:8090/userinfo",
+  "jwks_uri": "http://localhost:8090/jwks.json",
+  "response_types_supported": ["code"],
+  "response_modes_supported": ["query"],
+  "grant_types_supported": ["authorization_code"],
+  "subject_types_supported": ["public"],
+  "id_token_signing_alg_values_supported": ["ES256"],
+  "scopes_supported": ["openid", "profile", "email"],
+  "token_endpoint_auth_methods_supported": ["none", "client_secret_basic", "client_secret_post"],
+  "code_challenge_methods_supported": ["S256", "plain"],
+  "claims_supported": ["sub", "iss", "aud", "exp", "iat", "auth_time", "nonce", "name", "preferred_username", "email", "email_verified"]
+}"#,
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
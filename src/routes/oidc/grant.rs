@@ -0,0 +1,136 @@
+use crate::schema::{authorization_codes, users};
+use crate::utils::random_token;
+use crate::*;
+use chrono::{Duration, Utc};
+
+use super::{page, AuthorizeParams, CODE_MINUTES};
+
+/// The form of the user picker
+#[derive(Deserialize, Clone, JsonSchema)]
+pub struct GrantForm {
+    #[serde(flatten)]
+    pub params: AuthorizeParams,
+    /// The picked user. Missing when the login was cancelled
+    pub user_id: Option<String>,
+}
+
+/// Log in as the picked user
+#[post("/authorize")]
+pub async fn route(pool: web::Data<DbPool>, form: web::Form<GrantForm>) -> HttpResponse {
+    let GrantForm { params, user_id } = form.into_inner();
+
+    let (client_id, redirect_uri) = match params.validate() {
+        Ok((client_id, redirect_uri)) => (client_id.to_string(), redirect_uri.to_string()),
+        Err(e) => return e.respond(&params),
+    };
+
+    let Some(user_id) = user_id else {
+        return params.redirect(
+            &redirect_uri,
+            &[
+                ("error", "access_denied"),
+                ("error_description", "The login was cancelled"),
+            ],
+        );
+    };
+    let Ok(user_id) = user_id.parse::<i32>() else {
+        return user_not_found();
+    };
+
+    let now = Utc::now().naive_utc();
+    let new_code = AuthorizationCodeNew {
+        code: random_token(32),
+        client_id,
+        redirect_uri: redirect_uri.clone(),
+        user_id,
+        scope: params.scopes(),
+        nonce: params.nonce.clone(),
+        code_challenge: params.code_challenge.clone(),
+        code_challenge_method: params.code_challenge.as_ref().map(|_| {
+            params
+                .code_challenge_method
+                .clone()
+                .unwrap_or_else(|| "plain".to_string())
+        }),
+        expires_at: now + Duration::minutes(CODE_MINUTES),
+    };
+
+    let result = web::block({
+        let code = new_code.clone();
+        let mut db_connection = pool.get().unwrap();
+
+        move || {
+            db_connection.transaction(|connection| {
+                let exists = users::table
+                    .find(code.user_id)
+                    .first::<User>(connection)
+                    .optional()?
+                    .is_some();
+
+                if !exists {
+                    return Ok(false);
+                }
+
+                // The codes that were never exchanged
+                diesel::delete(
+                    authorization_codes::table.filter(authorization_codes::expires_at.le(now)),
+                )
+                .execute(connection)?;
+
+                diesel::insert_into(authorization_codes::table)
+                    .values(&code)
+                    .execute(connection)?;
+
+                Ok::<bool, diesel::result::Error>(true)
+            })
+        }
+    });
+
+    match result.await {
+        Ok(grant_result) => match grant_result {
+            Ok(true) => params.redirect(&redirect_uri, &[("code", &new_code.code)]),
+            Ok(false) => user_not_found(),
+            Err(e) => {
+                server_error(e);
+                HttpResponse::InternalServerError().finish()
+            }
+        },
+        Err(e) => {
+            server_error(e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+fn user_not_found() -> HttpResponse {
+    HttpResponse::NotFound()
+        .content_type("text/html; charset=utf-8")
+        .body(page(
+            "User not found",
+            "<p>The picked user doesn't exist anymore.</p>",
+        ))
+}
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "grantAuthorization",
+    resource: Resource::Oidc,
+    method: "POST",
+    path: "/authorize",
+    summary: "Log in as the picked user",
+    description: "The form of the `/authorize` page posts here, with the query of `/authorize` and the `user_id`. Redirects to the `redirect_uri` with a single-use `code` that expires in 5 minutes, or with `error=access_denied` without a `user_id`.",
+    params: &[],
+    query: &[],
+    headers: &[],
+    body: Some(
+        "response_type=code&client_id=...&redirect_uri=...&scope=...&state=...&user_id=1",
+    ),
+    body_schema: Some(|gen| gen.subschema_for::<GrantForm>()),
+    form: true,
+    data_schema: None,
+    returns: &[],
+    example_body: Some("response_type=code&client_id=my-spa&redirect_uri=http%3A%2F%2Flocalhost%3A3000%2Fcallback&scope=openid&state=af0ifjsldkj&user_id=1"),
+    example_response: "302 Found\nLocation: http://localhost:3000/callback?code=Hk4sWq9ZpL2xVn7RbT1mYc8dJf3Ga6Ue&state=af0ifjsldkj",
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
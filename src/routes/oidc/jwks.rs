@@ -0,0 +1,44 @@
+use crate::utils::SigningKey;
+use crate::*;
+
+/// Get the public key that signs the ID tokens
+#[get("/jwks.json")]
+pub async fn route(key: web::Data<SigningKey>) -> HttpResponse {
+    HttpResponse::Ok().json(Jwks {
+        keys: vec![key.jwk()],
+    })
+}
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "jwks",
+    resource: Resource::Oidc,
+    method: "GET",
+    path: "/jwks.json",
+    summary: "Get the public key that signs the ID tokens",
+    description: "A JSON Web Key Set with the ES256 key of the provider. A new key is generated every time the server starts. Not wrapped in the usual response object.",
+    params: &[],
+    query: &[],
+    headers: &[],
+    body: None,
+    body_schema: None,
+    form: false,
+    data_schema: None,
+    returns: &[],
+    example_body: None,
+    example_response: r#"{
+  "keys": [
+    {
+      "kty": "EC",
+      "crv": "P-256",
+      "x": "f83OJ3D2xF1Bg8vub9tLe1gHMzV76e8Tus9uPHvRVEU",
+      "y": "x_FEzRu9m36HLN_tue659LNpXW6pCyStikYjKIWI5a0",
+      "kid": "Tq3x9bXw2mQ",
+      "use": "sig",
+      "alg": "ES256"
+    }
+  ]
+}"#,
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
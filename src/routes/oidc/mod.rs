@@ -0,0 +1,214 @@
+pub mod authorize;
+pub mod configuration;
+pub mod grant;
+pub mod jwks;
+pub mod token;
+pub mod userinfo;
+
+use crate::*;
+use actix_web::http::header::{CACHE_CONTROL, LOCATION};
+use actix_web::http::StatusCode;
+
+/// How long an authorization code can be exchanged for tokens
+const CODE_MINUTES: i64 = 5;
+
+/// How long an ID token is valid
+const ID_TOKEN_MINUTES: i64 = 60;
+
+/// How long an access token works for `/userinfo`
+const ACCESS_TOKEN_MINUTES: i64 = 60;
+
+/// The scopes the provider knows. Other scopes are ignored
+const SCOPES: [&str; 3] = ["openid", "profile", "email"];
+
+/// Where the server is reached, like `http://localhost:8090`. It is also the issuer of the ID tokens
+fn issuer(req: &HttpRequest) -> String {
+    let connection_info = req.connection_info();
+    format!("{}://{}", connection_info.scheme(), connection_info.host())
+}
+
+/// The query of `/authorize`. The user picker sends it back in its form
+#[derive(Serialize, Deserialize, Clone, Debug, Default, JsonSchema)]
+pub struct AuthorizeParams {
+    /// Only `code` is supported
+    pub response_type: Option<String>,
+    /// Any client id is accepted
+    pub client_id: Option<String>,
+    /// Any absolute URL is accepted
+    pub redirect_uri: Option<String>,
+    /// Space separated, like `openid profile email`
+    pub scope: Option<String>,
+    pub state: Option<String>,
+    pub nonce: Option<String>,
+    pub code_challenge: Option<String>,
+    /// `S256` or `plain` [default: plain]
+    pub code_challenge_method: Option<String>,
+}
+
+/// Why an authorization request is rejected
+enum AuthorizeError {
+    /// The redirect URI can't be trusted, so the error is shown to the user
+    Page(&'static str),
+    /// The error is sent back to the redirect URI
+    Redirect {
+        error: &'static str,
+        description: &'static str,
+    },
+}
+
+impl AuthorizeParams {
+    /// Check the request. Returns the client id and the redirect URI
+    fn validate(&self) -> Result<(&str, &str), AuthorizeError> {
+        let Some(client_id) = self.client_id.as_deref().filter(|id| !id.is_empty()) else {
+            return Err(AuthorizeError::Page("The client_id is missing"));
+        };
+
+        let Some(redirect_uri) = self.redirect_uri.as_deref() else {
+            return Err(AuthorizeError::Page("The redirect_uri is missing"));
+        };
+        if !redirect_uri.contains("://") || redirect_uri.contains('#') {
+            return Err(AuthorizeError::Page(
+                "The redirect_uri has to be an absolute URL without a fragment",
+            ));
+        }
+
+        if self.response_type.as_deref() != Some("code") {
+            return Err(AuthorizeError::Redirect {
+                error: "unsupported_response_type",
+                description: "Only the code response type is supported",
+            });
+        }
+
+        match (
+            self.code_challenge.as_deref(),
+            self.code_challenge_method.as_deref(),
+        ) {
+            (None, Some(_)) => Err(AuthorizeError::Redirect {
+                error: "invalid_request",
+                description: "The code_challenge is missing",
+            }),
+            (Some(_), Some(method)) if method != "S256" && method != "plain" => {
+                Err(AuthorizeError::Redirect {
+                    error: "invalid_request",
+                    description: "The code_challenge_method has to be S256 or plain",
+                })
+            }
+            _ => Ok((client_id, redirect_uri)),
+        }
+    }
+
+    /// The known scopes that were asked for
+    fn scopes(&self) -> String {
+        let requested = self.scope.as_deref().unwrap_or_default();
+
+        SCOPES
+            .iter()
+            .filter(|scope| requested.split(' ').any(|requested| requested == **scope))
+            .copied()
+            .collect::<Vec<&str>>()
+            .join(" ")
+    }
+
+    /// Redirect back to the client, with the `state` of the request
+    fn redirect(&self, redirect_uri: &str, query: &[(&str, &str)]) -> HttpResponse {
+        let mut query = query.to_vec();
+        if let Some(state) = &self.state {
+            query.push(("state", state));
+        }
+
+        let separator = if redirect_uri.contains('?') { '&' } else { '?' };
+        let location = format!(
+            "{}{}{}",
+            redirect_uri,
+            separator,
+            serde_urlencoded::to_string(&query).unwrap_or_default()
+        );
+
+        HttpResponse::Found()
+            .insert_header((LOCATION, location))
+            .finish()
+    }
+}
+
+impl AuthorizeError {
+    fn respond(self, params: &AuthorizeParams) -> HttpResponse {
+        match self {
+            AuthorizeError::Page(msg) => HttpResponse::BadRequest()
+                .content_type("text/html; charset=utf-8")
+                .body(page(
+                    "Invalid request",
+                    &format!("<p>{}</p>", escape_html(msg)),
+                )),
+            AuthorizeError::Redirect { error, description } => params.redirect(
+                params.redirect_uri.as_deref().unwrap_or_default(),
+                &[("error", error), ("error_description", description)],
+            ),
+        }
+    }
+}
+
+/// The claims about the user that the scopes allow
+fn user_claims(user: &User, scope: &str) -> UserInfoJson {
+    let has_scope = |name: &str| scope.split(' ').any(|scope| scope == name);
+    let profile = has_scope("profile");
+    let email = has_scope("email") && user.email.is_some();
+
+    UserInfoJson {
+        sub: user.id.to_string(),
+        name: profile.then(|| user.name.clone()),
+        preferred_username: profile.then(|| user.username.clone()),
+        email: if email { user.email.clone() } else { None },
+        email_verified: email.then(|| user.email_verified_at.is_some()),
+    }
+}
+
+/// An error of `/token` or `/userinfo`
+fn oauth_error(status: StatusCode, error: &str, description: &str) -> HttpResponse {
+    HttpResponse::build(status)
+        .insert_header((CACHE_CONTROL, "no-store"))
+        .json(OAuthErrorJson {
+            error: error.to_string(),
+            error_description: description.to_string(),
+        })
+}
+
+/// Escape the text to put it in HTML
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// An HTML page of the provider
+fn page(title: &str, content: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{title} - KR Api</title>
+<style>
+  body {{ font-family: system-ui, sans-serif; background: #f4f4f5; color: #18181b; margin: 0; }}
+  main {{ max-width: 28rem; margin: 4rem auto; background: white; border-radius: 0.75rem; padding: 2rem; box-shadow: 0 1px 3px rgb(0 0 0 / 0.1); }}
+  h1 {{ font-size: 1.25rem; margin-top: 0; }}
+  p {{ color: #52525b; }}
+  button {{ display: block; width: 100%; text-align: left; font: inherit; padding: 0.75rem 1rem; margin: 0.5rem 0; border: 1px solid #e4e4e7; border-radius: 0.5rem; background: white; cursor: pointer; }}
+  button:hover {{ background: #f4f4f5; }}
+  button small {{ display: block; color: #71717a; }}
+  button.cancel {{ text-align: center; color: #71717a; }}
+</style>
+</head>
+<body>
+<main>
+<h1>{title}</h1>
+{content}
+</main>
+</body>
+</html>"#,
+        title = escape_html(title),
+        content = content,
+    )
+}
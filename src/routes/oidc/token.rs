@@ -0,0 +1,325 @@
+use crate::schema::{authorization_codes, oidc_access_tokens, users};
+use crate::utils::{check_code_verifier, random_token, SigningKey};
+use crate::*;
+use actix_web::http::header::{AUTHORIZATION, CACHE_CONTROL};
+use actix_web::http::StatusCode;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{Duration, Utc};
+use std::error::Error;
+
+use super::{issuer, oauth_error, user_claims, ACCESS_TOKEN_MINUTES, ID_TOKEN_MINUTES};
+
+/// The form of a token request
+#[derive(Deserialize, Clone, JsonSchema)]
+pub struct TokenRequest {
+    /// Only `authorization_code`
+    pub grant_type: Option<String>,
+    pub code: Option<String>,
+    pub redirect_uri: Option<String>,
+    /// Can also be sent with HTTP Basic authentication. A `client_secret` is ignored
+    pub client_id: Option<String>,
+    /// Needed when `/authorize` got a `code_challenge`
+    pub code_verifier: Option<String>,
+}
+
+/// How exchanging a code went
+enum Exchange {
+    Tokens(TokenJson),
+    InvalidGrant(&'static str),
+}
+
+/// Exchange an authorization code for tokens
+#[post("/token")]
+pub async fn route(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    key: web::Data<SigningKey>,
+    form: web::Form<TokenRequest>,
+) -> HttpResponse {
+    let form = form.into_inner();
+
+    if form.grant_type.as_deref() != Some("authorization_code") {
+        return oauth_error(
+            StatusCode::BAD_REQUEST,
+            "unsupported_grant_type",
+            "Only the authorization_code grant type is supported",
+        );
+    }
+
+    let Some(code) = form.code.clone() else {
+        return oauth_error(
+            StatusCode::BAD_REQUEST,
+            "invalid_request",
+            "The code is missing",
+        );
+    };
+
+    let Some(client_id) = form.client_id.clone().or_else(|| basic_client_id(&req)) else {
+        return oauth_error(
+            StatusCode::UNAUTHORIZED,
+            "invalid_client",
+            "The client_id is missing",
+        );
+    };
+
+    let issuer = issuer(&req);
+
+    let exchange = web::block({
+        let mut db_connection = pool.get().unwrap();
+
+        move || -> Result<Exchange, Box<dyn Error + Send + Sync + 'static>> {
+            let now = Utc::now().naive_utc();
+
+            let Some((authorization, user)) = authorization_codes::table
+                .inner_join(users::table)
+                .filter(authorization_codes::code.eq(&code))
+                .first::<(AuthorizationCode, User)>(&mut db_connection)
+                .optional()?
+            else {
+                return Ok(Exchange::InvalidGrant(
+                    "The code is invalid or already used",
+                ));
+            };
+
+            // A code works once, even when the exchange fails
+            diesel::delete(authorization_codes::table.find(authorization.id))
+                .execute(&mut db_connection)?;
+
+            if authorization.expires_at <= now {
+                return Ok(Exchange::InvalidGrant("The code is expired"));
+            }
+            if authorization.client_id != client_id {
+                return Ok(Exchange::InvalidGrant(
+                    "The code was issued to another client",
+                ));
+            }
+            if form.redirect_uri.as_deref() != Some(authorization.redirect_uri.as_str()) {
+                return Ok(Exchange::InvalidGrant(
+                    "The redirect_uri doesn't match the one of /authorize",
+                ));
+            }
+
+            if let Some(challenge) = &authorization.code_challenge {
+                let method = authorization.code_challenge_method.as_deref();
+                let is_valid = form.code_verifier.as_deref().is_some_and(|verifier| {
+                    check_code_verifier(verifier, challenge, method.unwrap_or("plain"))
+                });
+
+                if !is_valid {
+                    return Ok(Exchange::InvalidGrant(
+                        "The code_verifier is missing or incorrect",
+                    ));
+                }
+            }
+
+            // The expired access tokens of every user are deleted at the same time
+            diesel::delete(
+                oidc_access_tokens::table.filter(oidc_access_tokens::expires_at.le(now)),
+            )
+            .execute(&mut db_connection)?;
+
+            let access_token = OidcAccessTokenNew {
+                token: random_token(32),
+                client_id: client_id.clone(),
+                user_id: user.id,
+                scope: authorization.scope.clone(),
+                expires_at: now + Duration::minutes(ACCESS_TOKEN_MINUTES),
+            };
+
+            diesel::insert_into(oidc_access_tokens::table)
+                .values(&access_token)
+                .execute(&mut db_connection)?;
+
+            let has_openid = authorization
+                .scope
+                .split(' ')
+                .any(|scope| scope == "openid");
+            let id_token = if has_openid {
+                let claims = IdTokenClaims {
+                    iss: issuer,
+                    aud: client_id,
+                    iat: now.timestamp(),
+                    exp: (now + Duration::minutes(ID_TOKEN_MINUTES)).timestamp(),
+                    auth_time: authorization.created_at.timestamp(),
+                    nonce: authorization.nonce,
+                    user: user_claims(&user, &authorization.scope),
+                };
+
+                Some(key.sign(&claims)?)
+            } else {
+                None
+            };
+
+            Ok(Exchange::Tokens(TokenJson {
+                access_token: access_token.token,
+                token_type: "Bearer".to_string(),
+                expires_in: ACCESS_TOKEN_MINUTES * 60,
+                id_token,
+                scope: authorization.scope,
+            }))
+        }
+    });
+
+    match exchange.await {
+        Ok(exchange_result) => match exchange_result {
+            Ok(Exchange::Tokens(tokens)) => HttpResponse::Ok()
+                .insert_header((CACHE_CONTROL, "no-store"))
+                .json(tokens),
+            Ok(Exchange::InvalidGrant(description)) => {
+                oauth_error(StatusCode::BAD_REQUEST, "invalid_grant", description)
+            }
+            Err(e) => {
+                server_error(e);
+                oauth_error(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "server_error",
+                    "Something went wrong",
+                )
+            }
+        },
+        Err(e) => {
+            server_error(e);
+            oauth_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "server_error",
+                "Something went wrong",
+            )
+        }
+    }
+}
+
+/// The client id of HTTP Basic authentication, which confidential clients use
+fn basic_client_id(req: &HttpRequest) -> Option<String> {
+    let credentials = req
+        .headers()
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Basic ")?;
+    let credentials = String::from_utf8(STANDARD.decode(credentials.trim()).ok()?).ok()?;
+
+    credentials
+        .split_once(':')
+        .map(|(client_id, _)| client_id.to_string())
+}
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "token",
+    resource: Resource::Oidc,
+    method: "POST",
+    path: "/token",
+    summary: "Exchange an authorization code for tokens",
+    description: "Takes a form with `grant_type=authorization_code`, the `code`, the `redirect_uri`, the `client_id` and the PKCE `code_verifier` if `/authorize` got a `code_challenge`. Returns an access token that only works for `/userinfo` and lasts an hour, and, with the `openid` scope, an ES256 ID token. Not wrapped in the usual response object; errors are OAuth 2.0 errors with status 400.",
+    params: &[],
+    query: &[],
+    headers: &[],
+    body: Some(
+        "grant_type=authorization_code&code=...&redirect_uri=...&client_id=...&code_verifier=...",
+    ),
+    body_schema: Some(|gen| gen.subschema_for::<TokenRequest>()),
+    form: true,
+    data_schema: None,
+    returns: &[],
+    example_body: Some("grant_type=authorization_code&code=Hk4sWq9ZpL2xVn7RbT1mYc8dJf3Ga6Ue&redirect_uri=http%3A%2F%2Flocalhost%3A3000%2Fcallback&client_id=my-spa&code_verifier=dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+    example_response: r#"{
+  "access_token": "4bV1mQ8xT0cZr7LkP2sN9wYe3HjA6uDf",
+  "token_type": "Bearer",
+  "expires_in": 3600,
+  "id_token": "eyJhbGciOiJFUzI1NiIsInR5cCI6IkpXVCIsImtpZCI6IlRxM3g5Ylh3Mm1RIn0...",
+  "scope": "openid profile email"
+}"#,
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_pool;
+    use actix_web::{test, App};
+
+    /// The example of RFC 7636
+    const VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+    const CHALLENGE: &str = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+
+    fn authorize(code: &str, pool: &DbPool) {
+        let connection = &mut pool.get().unwrap();
+
+        diesel::insert_into(users::table)
+            .values(&UserNew {
+                name: "Shanto".to_string(),
+                username: format!("shanto-{}", code),
+                email: None,
+                img_url: None,
+                phone: None,
+                password: String::new(),
+            })
+            .execute(connection)
+            .unwrap();
+        let user_id = users::table
+            .select(users::id)
+            .order(users::id.desc())
+            .first(connection)
+            .unwrap();
+
+        diesel::insert_into(authorization_codes::table)
+            .values(&AuthorizationCodeNew {
+                code: code.to_string(),
+                client_id: "spa".to_string(),
+                redirect_uri: "http://localhost:3000/callback".to_string(),
+                user_id,
+                scope: "openid".to_string(),
+                nonce: None,
+                code_challenge: Some(CHALLENGE.to_string()),
+                code_challenge_method: Some("S256".to_string()),
+                expires_at: Utc::now().naive_utc() + Duration::minutes(5),
+            })
+            .execute(connection)
+            .unwrap();
+    }
+
+    fn token_request(code: &str, verifier: &str) -> test::TestRequest {
+        test::TestRequest::post().uri("/token").set_form([
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", "http://localhost:3000/callback"),
+            ("client_id", "spa"),
+            ("code_verifier", verifier),
+        ])
+    }
+
+    #[actix_web::test]
+    async fn rejects_a_wrong_code_verifier() {
+        let pool = test_pool();
+        authorize("first", &pool);
+        authorize("second", &pool);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(web::Data::new(SigningKey::generate().unwrap()))
+                .service(route),
+        )
+        .await;
+
+        let res = test::call_service(
+            &app,
+            token_request("first", "another verifier").to_request(),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let error: OAuthErrorJson = test::read_body_json(res).await;
+        assert_eq!(error.error, "invalid_grant");
+
+        // The code is used up, even with the right verifier now
+        let res = test::call_service(&app, token_request("first", VERIFIER).to_request()).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let res = test::call_service(&app, token_request("second", VERIFIER).to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let tokens: TokenJson = test::read_body_json(res).await;
+        assert!(tokens.id_token.is_some());
+    }
+}
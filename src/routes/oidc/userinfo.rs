@@ -0,0 +1,102 @@
+use crate::schema::{oidc_access_tokens, users};
+use crate::*;
+use actix_web::http::header::{HeaderValue, AUTHORIZATION, CACHE_CONTROL, WWW_AUTHENTICATE};
+use actix_web::http::StatusCode;
+use chrono::Utc;
+
+use super::{oauth_error, user_claims};
+
+/// Get the claims about the user of an access token
+#[get("/userinfo")]
+pub async fn route(req: HttpRequest, pool: web::Data<DbPool>) -> HttpResponse {
+    let Some(token) = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string())
+    else {
+        return invalid_token("The access token is missing");
+    };
+
+    let access_token = web::block({
+        let mut db_connection = pool.get().unwrap();
+
+        move || {
+            oidc_access_tokens::table
+                .inner_join(users::table)
+                .filter(oidc_access_tokens::token.eq(&token))
+                .filter(oidc_access_tokens::expires_at.gt(Utc::now().naive_utc()))
+                .first::<(OidcAccessToken, User)>(&mut db_connection)
+                .optional()
+        }
+    });
+
+    match access_token.await {
+        Ok(access_token_result) => match access_token_result {
+            Ok(Some((access_token, user))) => HttpResponse::Ok()
+                .insert_header((CACHE_CONTROL, "no-store"))
+                .json(user_claims(&user, &access_token.scope)),
+            Ok(None) => invalid_token("The access token is invalid or expired"),
+            Err(e) => {
+                server_error(e);
+                oauth_error(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "server_error",
+                    "Something went wrong",
+                )
+            }
+        },
+        Err(e) => {
+            server_error(e);
+            oauth_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "server_error",
+                "Something went wrong",
+            )
+        }
+    }
+}
+
+/// The error of a missing or bad access token, as the bearer token spec describes it
+fn invalid_token(description: &str) -> HttpResponse {
+    let mut response = oauth_error(StatusCode::UNAUTHORIZED, "invalid_token", description);
+    response.headers_mut().insert(
+        WWW_AUTHENTICATE,
+        HeaderValue::from_static(r#"Bearer error="invalid_token""#),
+    );
+
+    response
+}
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "userinfo",
+    resource: Resource::Oidc,
+    method: "GET",
+    path: "/userinfo",
+    summary: "Get the claims about the user of an access token",
+    description: "Takes the access token from `/token` as a bearer token and returns the claims its scope allows. Other sessions don't work here. Not wrapped in the usual response object; errors are OAuth 2.0 errors with status 401 and the `WWW-Authenticate` header.",
+    params: &[],
+    query: &[],
+    headers: &[Param {
+        name: "Authorization",
+        description: "`Bearer <access_token>` with the access token from `/token`",
+        example: "Bearer 4bV1mQ8xT0cZr7LkP2sN9wYe3HjA6uDf",
+    }],
+    body: None,
+    body_schema: None,
+    form: false,
+    data_schema: None,
+    returns: &[],
+    example_body: None,
+    example_response: r#"{
+  "sub": "1",
+  "name": "Shanto",
+  "preferred_username": "shanto",
+  "email": "shanto@example.com",
+  "email_verified": true
+}"#,
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
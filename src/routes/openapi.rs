@@ -0,0 +1,37 @@
+use crate::utils::openapi_spec;
+use crate::*;
+
+/// Get the OpenAPI 3 description of the server
+#[get("/openapi.json")]
+pub async fn route(req: HttpRequest) -> HttpResponse {
+    let connection_info = req.connection_info();
+    let server_url = format!("{}://{}", connection_info.scheme(), connection_info.host());
+
+    HttpResponse::Ok().json(openapi_spec(&server_url))
+}
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "getOpenApi",
+    resource: Resource::Docs,
+    method: "GET",
+    path: "/openapi.json",
+    summary: "Get the OpenAPI 3 description of the server",
+    description: "Describes every route, its body and its responses. Use it to generate typed clients or run contract tests. The document is not wrapped in the response envelope, and `krapi openapi` prints the same one.",
+    params: &[],
+    query: &[],
+    headers: &[],
+    body: None,
+    body_schema: None,
+    form: false,
+    data_schema: None,
+    returns: &[],
+    example_body: None,
+    example_response: r#"{
+  "openapi": "3.0.3",
+  "info": { "title": "KR Api", "version": "0.1.0" },
+  "paths": {}
+}"#,
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
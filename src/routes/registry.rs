@@ -0,0 +1,134 @@
+use crate::utils::response::ResponseType;
+use actix_web::web::ServiceConfig;
+use schemars::gen::SchemaGenerator;
+use schemars::schema::Schema;
+
+use super::{admin, auth, explorer, greet, guide, mailbox, oidc, openapi, user};
+
+/// The group a route belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resource {
+    Users,
+    Auth,
+    Oidc,
+    Mailbox,
+    Admin,
+    Greet,
+    Docs,
+}
+
+/// A path parameter, a query parameter or a header of a route
+#[derive(Debug, Clone, Copy)]
+pub struct Param {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub example: &'static str,
+}
+
+/// One of the responses a route can send
+#[derive(Debug, Clone, Copy)]
+pub struct Returns {
+    pub response_type: ResponseType,
+    pub description: &'static str,
+}
+
+/// Builds the JSON schema of a type, see [`SchemaGenerator::subschema_for`]
+pub type SchemaFn = fn(&mut SchemaGenerator) -> Schema;
+
+/// Everything about a route: how to register it and how to document it
+///
+/// Every route module has an `INFO` constant of this type, and all of them are listed in [`ROUTES`].
+/// The server registers the routes from [`ROUTES`] (see [`configure`]), `krapi docs` renders them
+/// and `krapi openapi` describes them. It is the only description of a route: the doc comment of a
+/// handler is just its summary.
+pub struct RouteInfo {
+    /// A unique name of the route in camelCase, used as the OpenAPI `operationId`
+    pub id: &'static str,
+    pub resource: Resource,
+    /// `GET` or `POST`
+    pub method: &'static str,
+    pub path: &'static str,
+    pub summary: &'static str,
+    pub description: &'static str,
+    pub params: &'static [Param],
+    /// Parameters of the query string, like `?token=`
+    pub query: &'static [Param],
+    pub headers: &'static [Param],
+    /// The shape of the JSON body
+    pub body: Option<&'static str>,
+    /// The schema of the JSON body
+    pub body_schema: Option<SchemaFn>,
+    /// The body is an `application/x-www-form-urlencoded` form instead of JSON
+    pub form: bool,
+    /// The schema of the `data` of a successful response
+    pub data_schema: Option<SchemaFn>,
+    pub returns: &'static [Returns],
+    pub example_body: Option<&'static str>,
+    pub example_response: &'static str,
+    /// Registers the route handler
+    pub register: fn(&mut ServiceConfig),
+}
+
+/// All the routes of the server
+pub const ROUTES: &[&RouteInfo] = &[
+    // users
+    &user::get_users::INFO,
+    &user::create_user::INFO,
+    &user::match_user::INFO,
+    &user::update_password::INFO,
+    &user::update_user::INFO,
+    &user::get_user::INFO,
+    // auth
+    &auth::me::INFO,
+    &auth::logout::INFO,
+    &auth::forgot_password::INFO,
+    &auth::reset_password::INFO,
+    &auth::verify_email::INFO,
+    &auth::resend_verification::INFO,
+    &auth::enroll_2fa::INFO,
+    &auth::confirm_2fa::INFO,
+    &auth::disable_2fa::INFO,
+    &auth::api_key::INFO,
+    // oidc
+    &oidc::configuration::INFO,
+    &oidc::authorize::INFO,
+    &oidc::grant::INFO,
+    &oidc::token::INFO,
+    &oidc::userinfo::INFO,
+    &oidc::jwks::INFO,
+    // mailbox
+    &mailbox::INFO,
+    // greet
+    &greet::INFO,
+    // docs
+    &openapi::INFO,
+    &explorer::INFO,
+    &guide::INFO,
+    // admin
+    &admin::reset::INFO,
+    &admin::list_snapshots::INFO,
+    &admin::save_snapshot::INFO,
+    &admin::restore_snapshot::INFO,
+];
+
+/// Register the [`ROUTES`]
+///
+/// The routes of the OpenID Connect provider are only registered with `oidc`.
+/// Call this from [`actix_web::App::configure`].
+pub fn configure(cfg: &mut ServiceConfig, oidc: bool) {
+    for route in ROUTES {
+        if route.resource == Resource::Oidc && !oidc {
+            continue;
+        }
+
+        (route.register)(cfg);
+    }
+}
+
+/// The routes of a resource
+pub fn routes_of(resource: Resource) -> impl Iterator<Item = &'static RouteInfo> {
+    ROUTES
+        .iter()
+        .copied()
+        .filter(move |route| route.resource == resource)
+}
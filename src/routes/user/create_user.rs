@@ -1,81 +1,24 @@
+use crate::routes::auth::send_verification_email;
 use crate::schema::users;
 use crate::utils::hash::hash_password;
 use crate::*;
 
 /// Create a new user
-///
-/// ## Route
-///
-/// `POST` localhost:8090/create-user
-///
-/// ## Body
-///
-/// ```json
-/// {
-///     "name": string,
-///     "username": string,
-///     "password": string,
-///     "email": string (optional),
-///     "img_url": string (optional),
-///     "phone": number (optional)
-/// }
-/// ```
-/// Required fields: `name`, `username` and `password`
-///
-/// ## Returns
-///
-/// - If successful, returns [`ResponseType::Success`](crate::utils::response::ResponseType::Success) with the data [`UserJson`].
-///
-/// - If user already exists (matched by `username` and `email`) then returns [`ResponseType::AlreadyExists`](crate::utils::response::ResponseType::AlreadyExists).
-///
-/// - If any error occurs, returns [`ResponseType::ServerError`](crate::utils::response::ResponseType::ServerError).
-///
-/// ## Example
-///
-/// Javascript Fetch API
-///
-/// ```js
-/// const res = await fetch("http://localhost:8090/create-user", {
-///    method: "POST",
-///    headers: {
-///     "Content-Type": "application/json",
-///   },
-///    body: JSON.stringify({
-///      name: "Shanto Islam",
-///      username: "shanto",
-///      email: "shanto@gmail.com",
-///      password: "admin005"
-///    }),
-/// });
-///
-/// const json = await res.json();
-/// const data = json.data;
-///
-/// console.log(data);
-/// ```
-///
-/// ## Example Response
-///
-/// ```json
-/// {
-///     "type": "Success",
-///     "msg": null,
-///     "data": {
-///       "name": "Shanto Islam",
-///       "username": "shanto",
-///       "email": "shanto@gmail.com",
-///       "created_at": "2023-05-21T07:30:48",
-///       "id": 223,
-///       "img_url": null,
-///       "phone": null
-///     }
-/// }
-/// ```
 #[post("/create-user")]
-pub async fn route(pool: web::Data<DbPool>, item: web::Json<UserNew>) -> HttpResponse {
+pub async fn route(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    item: web::Json<UserNew>,
+) -> HttpResponse {
     let mut db_connection = pool.get().unwrap();
     let new_user = item.into_inner();
 
+    // Where the link in the verification email points to
+    let base_url = {
+        let connection_info = req.connection_info();
+        format!("{}://{}", connection_info.scheme(), connection_info.host())
+    };
+
     // Check if username or email already exists
     let user_exists_result = web::block({
         let new_user = new_user.clone();
@@ -112,29 +55,21 @@ pub async fn route(pool: web::Data<DbPool>, item: web::Json<UserNew>) -> HttpRes
         ..new_user
     };
 
-    // Insert user into database
+    // Insert user into database, together with the verification email so a failure leaves neither behind
     let result = web::block(move || {
-        let insert_result = diesel::insert_into(crate::schema::users::table)
-            .values(&new_user)
-            .execute(&mut db_connection);
+        db_connection.transaction(|connection| {
+            diesel::insert_into(crate::schema::users::table)
+                .values(&new_user)
+                .execute(connection)?;
 
-        if let Err(e) = insert_result {
-            server_error(e);
-        }
+            // Get the user
+            let user = crate::schema::users::table
+                .order(crate::schema::users::id.desc())
+                .first::<User>(connection)?;
 
-        // Get the user
-        let user = crate::schema::users::table
-            .order(crate::schema::users::id.desc())
-            .first::<User>(&mut db_connection);
+            send_verification_email(&user, &base_url, connection)?;
 
-        user.map(|user| UserJson {
-            id: user.id,
-            name: user.name,
-            username: user.username,
-            email: user.email,
-            img_url: user.img_url,
-            phone: user.phone,
-            created_at: user.created_at,
+            Ok::<UserJson, diesel::result::Error>(user.into())
         })
     });
 
@@ -153,3 +88,67 @@ pub async fn route(pool: web::Data<DbPool>, item: web::Json<UserNew>) -> HttpRes
         }
     }
 }
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "createUser",
+    resource: Resource::Users,
+    method: "POST",
+    path: "/create-user",
+    summary: "Create a new user",
+    description: "Required fields: `name`, `username` and `password`. If the user has an email, a verification email is put in the local mailbox.",
+    params: &[],
+    query: &[],
+    headers: &[],
+    body: Some(
+        r#"{
+    "name": string,
+    "username": string,
+    "password": string,
+    "email": string (optional),
+    "img_url": string (optional),
+    "phone": number (optional)
+}"#,
+    ),
+    body_schema: Some(|gen| gen.subschema_for::<UserNew>()),
+    form: false,
+    data_schema: Some(|gen| gen.subschema_for::<UserJson>()),
+    returns: &[
+        Returns {
+            response_type: ResponseType::Success,
+            description: "with the data `UserJson`",
+        },
+        Returns {
+            response_type: ResponseType::AlreadyExists,
+            description: "if the username or email already exists",
+        },
+        Returns {
+            response_type: ResponseType::ServerError,
+            description: "if any error occurs",
+        },
+    ],
+    example_body: Some(
+        r#"{
+  "name": "Shanto Islam",
+  "username": "shanto",
+  "email": "shanto@gmail.com",
+  "password": "admin005"
+}"#,
+    ),
+    example_response: r#"{
+    "type": "Success",
+    "msg": null,
+    "data": {
+      "name": "Shanto Islam",
+      "username": "shanto",
+      "email": "shanto@gmail.com",
+      "created_at": "2023-05-21T07:30:48",
+      "email_verified_at": null,
+      "id": 223,
+      "img_url": null,
+      "phone": null
+    }
+}"#,
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
@@ -1,53 +1,6 @@
 use crate::*;
 
 /// Get a user by id
-///
-/// ## Route
-///
-/// `GET` localhost:8090/get-user/{id}
-///
-/// Here, `{id}` is the id of the user. It is an integer.
-///
-/// ## Returns
-///
-/// - If successful, returns [`ResponseType::Success`](crate::utils::response::ResponseType::Success) with the data [`UserJson`].
-///
-/// - If user does not exist, returns [`ResponseType::NotFound`](crate::utils::response::ResponseType::NotFound).
-///
-/// - If any error occurs, returns [`ResponseType::ServerError`](crate::utils::response::ResponseType::ServerError).
-///
-/// ## Example
-///
-/// Javascript Fetch API
-///
-/// ```js
-/// const res = await fetch("http://localhost:8090/get-user/223");
-///
-/// const json = await res.json();
-/// const data = json.data;
-///
-/// console.log(data);
-/// ```
-///
-/// ## Example Response
-///
-/// Suppose the user with id `223` exists.
-///
-/// ```json
-/// {
-///     "type": "Success",
-///     "msg": null,
-///     "data": {
-///       "name": "Shanto Islam",
-///       "username": "shanto",
-///       "email": "shanto@gmail.com",
-///       "created_at": "2023-05-21T07:30:48",
-///       "id": 223,
-///       "img_url": null,
-///       "phone": null
-///     }
-/// }
-/// ```
 #[get("/get-user/{id}")]
 pub async fn route(pool: web::Data<DbPool>, path: Path<i32>) -> HttpResponse {
     let mut db_connection = pool.get().unwrap();
@@ -62,22 +15,13 @@ pub async fn route(pool: web::Data<DbPool>, path: Path<i32>) -> HttpResponse {
 
     match result.await {
         Ok(users_result) => match users_result {
-            Ok(users) => {
+            Ok(mut users) => {
                 // if users is empty, return notfound
                 if users.is_empty() {
                     return Response::not_found().send();
                 }
 
-                let user = &users[0];
-                let user_json = UserJson {
-                    id: user.id,
-                    name: user.name.clone(),
-                    username: user.username.clone(),
-                    email: user.email.clone(),
-                    img_url: user.img_url.clone(),
-                    phone: user.phone,
-                    created_at: user.created_at,
-                };
+                let user_json: UserJson = users.swap_remove(0).into();
 
                 Response::success().data(user_json).send()
             }
@@ -92,3 +36,55 @@ pub async fn route(pool: web::Data<DbPool>, path: Path<i32>) -> HttpResponse {
         }
     }
 }
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "getUser",
+    resource: Resource::Users,
+    method: "GET",
+    path: "/get-user/{id}",
+    summary: "Get a user by id",
+    description: "Returns the user with the given id.",
+    params: &[Param {
+        name: "id",
+        description: "The id of the user. It is an integer.",
+        example: "223",
+    }],
+    query: &[],
+    headers: &[],
+    body: None,
+    body_schema: None,
+    form: false,
+    data_schema: Some(|gen| gen.subschema_for::<UserJson>()),
+    returns: &[
+        Returns {
+            response_type: ResponseType::Success,
+            description: "with the data `UserJson`",
+        },
+        Returns {
+            response_type: ResponseType::NotFound,
+            description: "if the user does not exist",
+        },
+        Returns {
+            response_type: ResponseType::ServerError,
+            description: "if any error occurs",
+        },
+    ],
+    example_body: None,
+    example_response: r#"{
+    "type": "Success",
+    "msg": null,
+    "data": {
+      "name": "Shanto Islam",
+      "username": "shanto",
+      "email": "shanto@gmail.com",
+      "created_at": "2023-05-21T07:30:48",
+      "email_verified_at": "2023-05-21T07:30:48",
+      "id": 223,
+      "img_url": null,
+      "phone": null
+    }
+}"#,
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
@@ -1,67 +1,6 @@
 use crate::*;
 
 /// Get all users
-///
-/// ## Route
-///
-/// `GET` localhost:8090/get-users
-///
-/// ## Returns
-///
-/// - If successful, returns [`ResponseType::Success`](crate::utils::response::ResponseType::Success) with the data [`Vec<UserJson>`].
-///
-/// - If any error occurs, returns [`ResponseType::ServerError`](crate::utils::response::ResponseType::ServerError).
-///
-/// ## Example
-///
-/// Javascript Fetch API
-///
-/// ```js
-/// const res = await fetch("http://localhost:8090/get-users");
-///
-/// const json = await res.json();
-/// const data = json.data;
-///
-/// console.log(data);
-/// ```
-///
-/// ## Example Response
-///
-/// ```json
-/// {
-///     "type": "Success",
-///     "msg": null,
-///     "data": [
-///         {
-///           "created_at": "2023-05-21T07:30:48",
-///           "email": "shanto@gmail.com",
-///           "id": 223,
-///           "img_url": null,
-///           "name": "Shanto Islam",
-///           "phone": null,
-///           "username": "shanto"
-///         },
-///         {
-///           "created_at": "2023-05-20T16:22:17",
-///           "email": "oleta_iste@hotmail.com",
-///           "id": 222,
-///           "img_url": null,
-///           "name": "Gunner Hettinger",
-///           "phone": null,
-///           "username": "otis_quaerat"
-///         },
-///         {
-///           "created_at": "2023-05-20T16:22:17",
-///           "email": null,
-///           "id": 221,
-///           "img_url": null,
-///           "name": "Ursula Ruecker",
-///           "phone": null,
-///           "username": "dianna_sed"
-///         }
-///      ]
-/// }
-/// ```
 #[get("/get-users")]
 pub async fn route(pool: web::Data<DbPool>) -> HttpResponse {
     let mut db_connection = pool.get().unwrap();
@@ -78,15 +17,7 @@ pub async fn route(pool: web::Data<DbPool>) -> HttpResponse {
             Ok(users) => {
                 let users_json = users
                     .into_iter()
-                    .map(|user| UserJson {
-                        id: user.id,
-                        name: user.name,
-                        username: user.username,
-                        email: user.email,
-                        img_url: user.img_url,
-                        phone: user.phone,
-                        created_at: user.created_at,
-                    })
+                    .map(UserJson::from)
                     .collect::<Vec<UserJson>>();
 
                 Response::success().data(users_json).send()
@@ -99,3 +30,49 @@ pub async fn route(pool: web::Data<DbPool>) -> HttpResponse {
         Err(_) => Response::server_error().send(),
     }
 }
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "getUsers",
+    resource: Resource::Users,
+    method: "GET",
+    path: "/get-users",
+    summary: "Get all users",
+    description: "Returns all the users, newest first.",
+    params: &[],
+    query: &[],
+    headers: &[],
+    body: None,
+    body_schema: None,
+    form: false,
+    data_schema: Some(|gen| gen.subschema_for::<Vec<UserJson>>()),
+    returns: &[
+        Returns {
+            response_type: ResponseType::Success,
+            description: "with the data `UserJson[]`",
+        },
+        Returns {
+            response_type: ResponseType::ServerError,
+            description: "if any error occurs",
+        },
+    ],
+    example_body: None,
+    example_response: r#"{
+    "type": "Success",
+    "msg": null,
+    "data": [
+        {
+          "created_at": "2023-05-21T07:30:48",
+          "email": "shanto@gmail.com",
+          "email_verified_at": "2023-05-21T07:30:48",
+          "id": 223,
+          "img_url": null,
+          "name": "Shanto Islam",
+          "phone": null,
+          "username": "shanto"
+        }
+     ]
+}"#,
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
@@ -1,78 +1,34 @@
+use crate::routes::auth::{
+    check_login_lock, check_second_factor, client_ip, login_failed, start_session, LoginOptions,
+    SecondFactor, SessionOptions,
+};
 use crate::schema::users;
+use crate::utils::clear_login_failures;
+use crate::utils::hash::{hash_password, needs_rehash, verify_password};
 use crate::*;
-use argon2::{
-    password_hash::{PasswordHash, PasswordVerifier},
-    Argon2,
-};
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Clone, JsonSchema)]
 pub struct MatchUser {
     pub username: String,
     pub password: String,
+    /// Keep the session in an `HttpOnly` cookie instead of returning a bearer token
+    #[serde(default)]
+    pub cookie: bool,
+    /// The code from the authenticator app, or a recovery code. Only needed with two-factor authentication
+    pub code: Option<String>,
 }
 
-/// Verify the user's password
-///
-/// ## Route
-///
-/// `POST` localhost:8090/verify-user
-///
-/// ## Body
-///
-/// ```json
-/// {
-///     "username": string,
-///     "password": string
-/// }
-/// ```
-///
-/// ## Returns
-///
-/// - If successful, returns [`ResponseType::Success`](crate::utils::response::ResponseType::Success).
-///
-/// - If user not found, returns [`ResponseType::NotFound`](crate::utils::response::ResponseType::NotFound).
-///
-/// - If password is incorrect, returns [`ResponseType::IncorrectPassword`](crate::utils::response::ResponseType::IncorrectPassword).
-///
-/// - If any error occurs, returns [`ResponseType::ServerError`](crate::utils::response::ResponseType::ServerError).
-///
-/// ## Example
-///
-/// First you need to create a user. See [`create_user`](crate::routes::create_user_route) route.
-///
-/// Lets say you have a user with username `shanto` and password `admin005`.
-///
-/// Javascript Fetch API
-///
-/// ```js
-/// const res = await fetch("http://localhost:8090/verify-user", {
-///   method: "POST",
-///   headers: {
-///    "Content-Type": "application/json",
-/// },
-///   body: JSON.stringify({
-///     username: "shanto",
-///     password: "admin005"
-///  }),
-/// });
-///
-/// const json = await res.json();
-/// const data = json.data;
-///
-/// console.log(data);
-/// ```
-///
-/// ## Example Response
-///
-/// ```json
-/// {
-///   "type": "Success",
-///   "msg": "Login successful"
-/// }
-/// ```
+/// Verify the user's password and start a session
 #[post("/verify-user")]
-pub async fn route(pool: web::Data<DbPool>, item: web::Json<MatchUser>) -> HttpResponse {
+pub async fn route(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    options: web::Data<SessionOptions>,
+    login: web::Data<LoginOptions>,
+    item: web::Json<MatchUser>,
+) -> HttpResponse {
     let user_info = item.into_inner();
+    let ip = client_ip(&req);
 
     // Check if the user exists
     let user_exists = web::block({
@@ -83,16 +39,16 @@ pub async fn route(pool: web::Data<DbPool>, item: web::Json<MatchUser>) -> HttpR
             users::table
                 .filter(users::username.eq(&user_info.username))
                 .first::<User>(&mut db_connection)
+                .optional()
         }
     });
 
-    let user: User = match user_exists.await {
+    let user: Option<User> = match user_exists.await {
         Ok(user_result) => match user_result {
             Ok(user) => user,
-            Err(_) => {
-                return Response::not_found()
-                    .msg("Username or password is incorrect")
-                    .send();
+            Err(e) => {
+                server_error(e);
+                return Response::server_error().send();
             }
         },
         Err(e) => {
@@ -101,17 +57,194 @@ pub async fn route(pool: web::Data<DbPool>, item: web::Json<MatchUser>) -> HttpR
         }
     };
 
-    // Verify password
-    let password = user_info.password.as_bytes();
-    let hash = PasswordHash::new(&user.password).unwrap();
+    // Check if there were too many failed logins
+    if let Err(response) = check_login_lock(
+        &pool,
+        user.as_ref().map(|user| user.id),
+        &ip,
+        &login.lockout,
+    )
+    .await
+    {
+        return response;
+    }
 
-    let is_valid = Argon2::default().verify_password(password, &hash).is_ok();
+    let Some(user) = user else {
+        let response = Response::not_found().msg("Username or password is incorrect");
+        return login_failed(&pool, None, &ip, &login.lockout, response).await;
+    };
 
-    if !is_valid {
-        return Response::incorrect_password()
-            .msg("Username or password is incorrect")
+    // Verify password
+    if !verify_password(&user_info.password, &user.password) {
+        let response = Response::incorrect_password().msg("Username or password is incorrect");
+        return login_failed(&pool, Some(user.id), &ip, &login.lockout, response).await;
+    }
+
+    if login.require_verified_email && user.email_verified_at.is_none() {
+        return Response::no_permission()
+            .msg("Verify your email before logging in")
             .send();
     }
 
-    Response::success().msg("Login successful").send()
+    // Check the second factor
+    let second_factor = web::block({
+        let user = user.clone();
+        let code = user_info.code.clone();
+        let mut db_connection = pool.get().unwrap();
+
+        move || check_second_factor(&user, code.as_deref(), &mut db_connection)
+    });
+
+    match second_factor.await {
+        Ok(second_factor_result) => match second_factor_result {
+            Ok(SecondFactor::NotEnabled | SecondFactor::Correct) => (),
+            Ok(SecondFactor::Missing) => {
+                return Response::two_factor_required()
+                    .msg("Enter the code from your authenticator app")
+                    .send();
+            }
+            Ok(SecondFactor::Incorrect) => {
+                let response = Response::incorrect_password().msg("The code is incorrect");
+                return login_failed(&pool, Some(user.id), &ip, &login.lockout, response).await;
+            }
+            Err(e) => {
+                server_error(e);
+                return Response::server_error().send();
+            }
+        },
+        Err(e) => {
+            server_error(e);
+            return Response::server_error().send();
+        }
+    }
+
+    // Start the session
+    let session = web::block({
+        let mut db_connection = pool.get().unwrap();
+        let ttl = options.ttl;
+        let password = user_info.password.clone();
+
+        move || {
+            // Hash the password again when the hash cost has changed since it was hashed
+            if needs_rehash(&user.password) {
+                diesel::update(users::table.filter(users::id.eq(user.id)))
+                    .set(users::password.eq(hash_password(&password)))
+                    .execute(&mut db_connection)?;
+            }
+
+            clear_login_failures(user.id, &mut db_connection)?;
+            start_session(user.id, ttl, &mut db_connection)
+        }
+    });
+
+    match session.await {
+        Ok(session_result) => match session_result {
+            Ok(session) => {
+                let session_json = if user_info.cookie {
+                    SessionJson {
+                        token: None,
+                        csrf_token: Some(session.csrf_token.clone()),
+                        expires_at: session.expires_at,
+                    }
+                } else {
+                    SessionJson {
+                        token: Some(session.token.clone()),
+                        csrf_token: None,
+                        expires_at: session.expires_at,
+                    }
+                };
+
+                let mut response = Response::success()
+                    .msg("Login successful")
+                    .data(session_json)
+                    .send();
+
+                if user_info.cookie {
+                    options.set_cookies(&mut response, &session);
+                }
+
+                response
+            }
+            Err(e) => {
+                server_error(e);
+                Response::server_error().send()
+            }
+        },
+        Err(e) => {
+            server_error(e);
+            Response::server_error().send()
+        }
+    }
 }
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "verifyUser",
+    resource: Resource::Users,
+    method: "POST",
+    path: "/verify-user",
+    summary: "Verify the user's password and start a session",
+    description: "Checks the username and password of a user. Returns a bearer token for the `Authorization: Bearer <token>` header. With `\"cookie\": true`, the session is kept in an `HttpOnly` cookie instead and a CSRF token is returned, which is also set in the `krapi_session_csrf` cookie; requests that change something have to send it in the `X-CSRF-Token` header. Users with two-factor authentication need the `code` from the authenticator app, or a recovery code. The account is locked for `--lockout-period` (15 minutes) after `--lockout-attempts` (5) failed logins, and `--lockout-ip-attempts` locks the logins from an IP the same way; a successful login or a password reset starts the count again. A password hashed with another cost than `--hash-memory` is hashed again.",
+    params: &[],
+    query: &[],
+    headers: &[],
+    body: Some(
+        r#"{
+    "username": string,
+    "password": string,
+    "cookie"?: boolean,
+    "code"?: string
+}"#,
+    ),
+    body_schema: Some(|gen| gen.subschema_for::<MatchUser>()),
+    form: false,
+    data_schema: Some(|gen| gen.subschema_for::<SessionJson>()),
+    returns: &[
+        Returns {
+            response_type: ResponseType::Success,
+            description: "with the data `SessionJson`",
+        },
+        Returns {
+            response_type: ResponseType::NotFound,
+            description: "with the data `LoginAttemptsJson`, if the user does not exist",
+        },
+        Returns {
+            response_type: ResponseType::IncorrectPassword,
+            description: "with the data `LoginAttemptsJson`, if the password or the code is incorrect",
+        },
+        Returns {
+            response_type: ResponseType::NoPermission,
+            description: "if the email is not verified and `--require-verified-email` is on",
+        },
+        Returns {
+            response_type: ResponseType::TwoFactorRequired,
+            description: "if the user has two-factor authentication and no code was given",
+        },
+        Returns {
+            response_type: ResponseType::AccountLocked,
+            description: "with the data `LockoutJson`, if too many logins failed",
+        },
+        Returns {
+            response_type: ResponseType::ServerError,
+            description: "if any error occurs",
+        },
+    ],
+    example_body: Some(
+        r#"{
+  "username": "shanto",
+  "password": "admin005",
+  "cookie": true
+}"#,
+    ),
+    example_response: r#"{
+  "type": "Success",
+  "msg": "Login successful",
+  "data": {
+    "token": null,
+    "csrf_token": "Xo2pLr8QeT5sVb1nKc7MzW4yHg9dFa3U",
+    "expires_at": "2023-05-28T07:30:48.123456"
+  }
+}"#,
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
@@ -1,81 +1,30 @@
-use crate::schema::users;
-use crate::*;
-use argon2::{
-    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
-    Argon2,
+use crate::routes::auth::{
+    check_login_lock, check_second_factor, client_ip, login_failed, LoginOptions, SecondFactor,
 };
+use crate::schema::{sessions, users};
+use crate::utils::clear_login_failures;
+use crate::utils::hash::{hash_password, verify_password};
+use crate::*;
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Clone, JsonSchema)]
 pub struct Password {
     username: String,
     password: String,
     new_password: String,
+    /// The code from the authenticator app, or a recovery code. Only needed with two-factor authentication
+    code: Option<String>,
 }
 
 /// Update the user's password
-///
-/// ## Route
-///
-/// `POST` localhost:8090/update-password
-///
-/// ## Body
-///
-/// ```json
-/// {
-///    "username": string,
-///    "password": string,
-///    "new_password": string
-/// }
-/// ```
-///
-/// ## Returns
-///
-/// - If successful, returns [`ResponseType::Success`](crate::utils::response::ResponseType::Success).
-///
-/// - If user not found, returns [`ResponseType::NotFound`](crate::utils::response::ResponseType::NotFound).
-///
-/// - If password is incorrect, returns [`ResponseType::IncorrectPassword`](crate::utils::response::ResponseType::IncorrectPassword).
-///
-/// - If any error occurs, returns [`ResponseType::ServerError`](crate::utils::response::ResponseType::ServerError).
-///
-/// ## Example
-///
-/// First you need to create a user. See [`create_user`](crate::routes::create_user_route) route.
-///
-/// Lets say you have a user with username `shanto` and password `admin005`.
-///
-/// Javascript Fetch API
-///
-/// ```js
-/// const res = await fetch("http://localhost:8090/update-password", {
-///   method: "POST",
-///   headers: {
-///     "Content-Type": "application/json",
-/// },
-///   body: JSON.stringify({
-///      username: "shanto",
-///      password: "admin005",
-///      new_password: "admin006"
-///  }),
-/// });
-///
-/// const json = await res.json();
-/// const data = json.data;
-///
-/// console.log(data);
-/// ```
-///
-/// ## Example Response
-///
-/// ```json
-/// {
-///    "type": "Success",
-///    "msg": "Password updated successfully"
-/// }
-/// ```
 #[post("/update-password")]
-pub async fn route(pool: web::Data<DbPool>, item: web::Json<Password>) -> HttpResponse {
+pub async fn route(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    login: web::Data<LoginOptions>,
+    item: web::Json<Password>,
+) -> HttpResponse {
     let user_info = item.into_inner();
+    let ip = client_ip(&req);
 
     // Check if the user exists
     let user_exists = web::block({
@@ -86,15 +35,16 @@ pub async fn route(pool: web::Data<DbPool>, item: web::Json<Password>) -> HttpRe
             users::table
                 .filter(users::username.eq(&user_info.username))
                 .first::<User>(&mut db_connection)
+                .optional()
         }
     });
 
-    let user: User = match user_exists.await {
+    let user: Option<User> = match user_exists.await {
         Ok(user_result) => match user_result {
             Ok(user) => user,
-            Err(_) => {
-                // user not found
-                return Response::not_found().msg("User not found").send();
+            Err(e) => {
+                server_error(e);
+                return Response::server_error().send();
             }
         },
         Err(e) => {
@@ -103,43 +53,90 @@ pub async fn route(pool: web::Data<DbPool>, item: web::Json<Password>) -> HttpRe
         }
     };
 
+    // Check if there were too many failed logins
+    if let Err(response) = check_login_lock(
+        &pool,
+        user.as_ref().map(|user| user.id),
+        &ip,
+        &login.lockout,
+    )
+    .await
+    {
+        return response;
+    }
+
+    let Some(user) = user else {
+        let response = Response::not_found().msg("User not found");
+        return login_failed(&pool, None, &ip, &login.lockout, response).await;
+    };
+
     // Verify password
-    let password = user_info.password.as_bytes();
-    let hash = PasswordHash::new(&user.password).unwrap();
+    if !verify_password(&user_info.password, &user.password) {
+        let response = Response::incorrect_password().msg("Username or password is incorrect");
+        return login_failed(&pool, Some(user.id), &ip, &login.lockout, response).await;
+    }
 
-    let is_valid = Argon2::default().verify_password(password, &hash).is_ok();
+    // Check the second factor
+    let second_factor = web::block({
+        let user = user.clone();
+        let code = user_info.code.clone();
+        let mut db_connection = pool.get().unwrap();
 
-    if !is_valid {
-        return Response::incorrect_password()
-            .msg("Username or password is incorrect")
-            .send();
+        move || check_second_factor(&user, code.as_deref(), &mut db_connection)
+    });
+
+    match second_factor.await {
+        Ok(second_factor_result) => match second_factor_result {
+            Ok(SecondFactor::NotEnabled | SecondFactor::Correct) => (),
+            Ok(SecondFactor::Missing) => {
+                return Response::two_factor_required()
+                    .msg("Enter the code from your authenticator app")
+                    .send();
+            }
+            Ok(SecondFactor::Incorrect) => {
+                let response = Response::incorrect_password().msg("The code is incorrect");
+                return login_failed(&pool, Some(user.id), &ip, &login.lockout, response).await;
+            }
+            Err(e) => {
+                server_error(e);
+                return Response::server_error().send();
+            }
+        },
+        Err(e) => {
+            server_error(e);
+            return Response::server_error().send();
+        }
     }
 
     // Update password
-    let new_password = user_info.new_password.as_bytes();
-    let argon2 = Argon2::default();
-    let salt = SaltString::generate(&mut OsRng);
-
-    // hashed password
-    let hash = argon2
-        .hash_password(new_password, &salt)
-        .unwrap()
-        .to_string();
+    let hash = hash_password(&user_info.new_password);
 
     let update_password = web::block({
-        let user_info = user_info.clone();
         let mut db_connection = pool.get().unwrap();
 
         move || {
-            diesel::update(users::table)
-                .filter(users::username.eq(&user_info.username))
-                .set(users::password.eq(&hash))
-                .execute(&mut db_connection)
+            db_connection.transaction(|connection| {
+                diesel::update(users::table.filter(users::id.eq(user.id)))
+                    .set(users::password.eq(&hash))
+                    .execute(connection)?;
+
+                // Whoever knew the old password is logged out
+                diesel::delete(sessions::table.filter(sessions::user_id.eq(user.id)))
+                    .execute(connection)?;
+
+                clear_login_failures(user.id, connection)
+            })
         }
     });
 
     match update_password.await {
-        Ok(_) => (),
+        Ok(update_result) => match update_result {
+            Ok(_) => (),
+            Err(e) => {
+                server_error(e);
+                return Response::server_error().send();
+            }
+        },
         Err(e) => {
             server_error(e);
             return Response::server_error().send();
@@ -150,3 +147,132 @@ pub async fn route(pool: web::Data<DbPool>, item: web::Json<Password>) -> HttpRe
         .msg("Password updated successful")
         .send()
 }
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "updatePassword",
+    resource: Resource::Users,
+    method: "POST",
+    path: "/update-password",
+    summary: "Update the user's password",
+    description: "The current password is required to set a new one. Failed attempts count towards the lockout, and users with two-factor authentication need the `code` too. Every session of the user is logged out.",
+    params: &[],
+    query: &[],
+    headers: &[],
+    body: Some(
+        r#"{
+   "username": string,
+   "password": string,
+   "new_password": string,
+   "code"?: string
+}"#,
+    ),
+    body_schema: Some(|gen| gen.subschema_for::<Password>()),
+    form: false,
+    data_schema: None,
+    returns: &[
+        Returns {
+            response_type: ResponseType::Success,
+            description: "if the password was updated",
+        },
+        Returns {
+            response_type: ResponseType::NotFound,
+            description: "with the data `LoginAttemptsJson`, if the user does not exist",
+        },
+        Returns {
+            response_type: ResponseType::IncorrectPassword,
+            description: "with the data `LoginAttemptsJson`, if the password or the code is incorrect",
+        },
+        Returns {
+            response_type: ResponseType::TwoFactorRequired,
+            description: "if the user has two-factor authentication and no code was given",
+        },
+        Returns {
+            response_type: ResponseType::AccountLocked,
+            description: "with the data `LockoutJson`, if too many logins failed",
+        },
+        Returns {
+            response_type: ResponseType::ServerError,
+            description: "if any error occurs",
+        },
+    ],
+    example_body: Some(
+        r#"{
+  "username": "shanto",
+  "password": "admin005",
+  "new_password": "admin006"
+}"#,
+    ),
+    example_response: r#"{
+   "type": "Success",
+   "msg": "Password updated successfully"
+}"#,
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_pool;
+    use actix_web::http::StatusCode;
+    use actix_web::{test, App};
+    use chrono::{Duration, Utc};
+
+    #[actix_web::test]
+    async fn logs_out_every_session() {
+        let pool = test_pool();
+
+        {
+            let connection = &mut pool.get().unwrap();
+
+            diesel::insert_into(users::table)
+                .values(&UserNew {
+                    name: "Shanto".to_string(),
+                    username: "shanto".to_string(),
+                    email: None,
+                    img_url: None,
+                    phone: None,
+                    password: hash_password("admin005"),
+                })
+                .execute(connection)
+                .unwrap();
+            let user_id = users::table.select(users::id).first(connection).unwrap();
+
+            diesel::insert_into(sessions::table)
+                .values(&SessionNew {
+                    token: "token".to_string(),
+                    csrf_token: "csrf".to_string(),
+                    user_id,
+                    expires_at: Utc::now().naive_utc() + Duration::days(1),
+                })
+                .execute(connection)
+                .unwrap();
+        }
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(web::Data::new(LoginOptions::default()))
+                .service(route),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/update-password")
+            .set_json(serde_json::json!({
+                "username": "shanto",
+                "password": "admin005",
+                "new_password": "admin006",
+            }))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let sessions = sessions::table
+            .count()
+            .get_result::<i64>(&mut pool.get().unwrap())
+            .unwrap();
+        assert_eq!(sessions, 0);
+    }
+}
@@ -1,7 +1,8 @@
+use crate::routes::auth::send_verification_email;
 use crate::schema::users;
 use crate::*;
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Clone, JsonSchema)]
 pub struct UserUpdate {
     username: String, // For querying
     name: Option<String>,
@@ -11,81 +12,16 @@ pub struct UserUpdate {
 }
 
 /// Update the user
-///
-/// This route will update these fields:
-///
-/// - `name`
-/// - `email`
-/// - `phone`
-/// - `img_url`
-///
-/// For updating the password, see [`update_password`](crate::routes::update_password_route) route.
-///
-/// ## Route
-///
-/// `POST` localhost:8090/update-user
-///
-/// ## Body
-///
-/// ```json
-/// {
-///    "username": string,
-///    "name": string (optional),
-///    "email": string (optional),
-///    "phone": number (optional),
-///    "img_url": string (optional)
-/// }
-/// ```
-///
-/// The `username` field is required. This is used to query the user. Whichever field you want to update, just add it to the body.
-///
-/// ## Returns
-///
-/// - If successful, returns [`ResponseType::Success`](crate::utils::response::ResponseType::Success).
-///
-/// - If user not found, returns [`ResponseType::NotFound`](crate::utils::response::ResponseType::NotFound).
-///
-/// - If any error occurs, returns [`ResponseType::ServerError`](crate::utils::response::ResponseType::ServerError).
-///
-/// ## Example
-///
-/// First you need to create a user. See [`create_user`](crate::routes::create_user_route) route.
-///
-/// Lets say you have a user with username `shanto` and password `admin005` and email `shanto@xyz.com`.
-///
-/// Javascript Fetch API
-///
-/// ```js
-/// const res = await fetch("http://localhost:8090/update-user", {
-///   method: "POST",
-///   headers: {
-///     "Content-Type": "application/json",
-///   },
-///   body: JSON.stringify({
-///     username: "shanto",
-///     email: "shanto@abc.com",
-///  }),
-/// });
-///
-/// const json = await res.json();
-/// const data = json.data;
-///
-/// console.log(data);
-/// ```
-/// ## Example Response
-///
-/// ```json
-/// {
-///    "type": "Success",
-///    "msg": "Update successful"
-/// }
-/// ```
-///
-/// Now if you query the user using [`get_user`](crate::routes::get_user_route) route, you will see that the email has been updated.
 #[post("/update-user")]
-pub async fn route(pool: Data<DbPool>, item: Json<UserUpdate>) -> HttpResponse {
+pub async fn route(req: HttpRequest, pool: Data<DbPool>, item: Json<UserUpdate>) -> HttpResponse {
     let user_info = item.into_inner();
 
+    // Where the link in the verification email points to
+    let base_url = {
+        let connection_info = req.connection_info();
+        format!("{}://{}", connection_info.scheme(), connection_info.host())
+    };
+
     // Check if the user exists
     let user_exists = web::block({
         let user_info = user_info.clone();
@@ -117,26 +53,43 @@ pub async fn route(pool: Data<DbPool>, item: Json<UserUpdate>) -> HttpResponse {
         let mut db_connection = pool.get().unwrap();
 
         move || {
-            diesel::update(users::table.find(user.id))
-                .set((
-                    users::name.eq(user_info.name.unwrap_or(user.name)),
-                    users::email.eq(if user_info.email.is_some() {
-                        user_info.email
-                    } else {
-                        user.email
-                    }),
-                    users::phone.eq(if user_info.phone.is_some() {
-                        user_info.phone
-                    } else {
-                        user.phone
-                    }),
-                    users::img_url.eq(if user_info.img_url.is_some() {
-                        user_info.img_url
-                    } else {
-                        user.img_url
-                    }),
-                ))
-                .execute(&mut db_connection)
+            let email_changed = user_info.email.is_some() && user_info.email != user.email;
+
+            db_connection.transaction(|connection| {
+                diesel::update(users::table.find(user.id))
+                    .set((
+                        users::name.eq(user_info.name.unwrap_or(user.name)),
+                        users::email.eq(if user_info.email.is_some() {
+                            user_info.email
+                        } else {
+                            user.email
+                        }),
+                        users::phone.eq(if user_info.phone.is_some() {
+                            user_info.phone
+                        } else {
+                            user.phone
+                        }),
+                        users::img_url.eq(if user_info.img_url.is_some() {
+                            user_info.img_url
+                        } else {
+                            user.img_url
+                        }),
+                    ))
+                    .execute(connection)?;
+
+                // The new email has to be verified again
+                if email_changed {
+                    diesel::update(users::table.find(user.id))
+                        .set(users::email_verified_at.eq(None::<chrono::NaiveDateTime>))
+                        .execute(connection)?;
+
+                    let user = users::table.find(user.id).first::<User>(connection)?;
+
+                    send_verification_email(&user, &base_url, connection)?;
+                }
+
+                Ok::<(), diesel::result::Error>(())
+            })
         }
     });
 
@@ -151,3 +104,54 @@ pub async fn route(pool: Data<DbPool>, item: Json<UserUpdate>) -> HttpResponse {
         Err(_) => Response::server_error().send(),
     }
 }
+
+pub const INFO: RouteInfo = RouteInfo {
+    id: "updateUser",
+    resource: Resource::Users,
+    method: "POST",
+    path: "/update-user",
+    summary: "Update the user",
+    description: "Updates `name`, `email`, `phone` and `img_url`. The `username` is used to find the user. Whichever field you want to update, just add it to the body. A new email has to be verified again; the verification email goes to `/__mailbox`. For the password, see `/update-password`.",
+    params: &[],
+    query: &[],
+    headers: &[],
+    body: Some(
+        r#"{
+   "username": string,
+   "name": string (optional),
+   "email": string (optional),
+   "phone": number (optional),
+   "img_url": string (optional)
+}"#,
+    ),
+    body_schema: Some(|gen| gen.subschema_for::<UserUpdate>()),
+    form: false,
+    data_schema: None,
+    returns: &[
+        Returns {
+            response_type: ResponseType::Success,
+            description: "if the user was updated",
+        },
+        Returns {
+            response_type: ResponseType::NotFound,
+            description: "if the user does not exist",
+        },
+        Returns {
+            response_type: ResponseType::ServerError,
+            description: "if any error occurs",
+        },
+    ],
+    example_body: Some(
+        r#"{
+  "username": "shanto",
+  "email": "shanto@abc.com"
+}"#,
+    ),
+    example_response: r#"{
+   "type": "Success",
+   "msg": "Update successful"
+}"#,
+    register: |cfg| {
+        cfg.service(route);
+    },
+};
@@ -1,5 +1,114 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    api_keys (id) {
+        id -> Integer,
+        name -> Text,
+        prefix -> Text,
+        key_hash -> Text,
+        scopes -> Text,
+        created_at -> Timestamp,
+        expires_at -> Nullable<Timestamp>,
+        last_used_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    authorization_codes (id) {
+        id -> Integer,
+        code -> Text,
+        client_id -> Text,
+        redirect_uri -> Text,
+        user_id -> Integer,
+        scope -> Text,
+        nonce -> Nullable<Text>,
+        code_challenge -> Nullable<Text>,
+        code_challenge_method -> Nullable<Text>,
+        created_at -> Timestamp,
+        expires_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    email_verifications (id) {
+        id -> Integer,
+        token -> Text,
+        user_id -> Integer,
+        created_at -> Timestamp,
+        expires_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    emails (id) {
+        id -> Integer,
+        recipient -> Text,
+        subject -> Text,
+        body -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    failed_logins (id) {
+        id -> Integer,
+        user_id -> Nullable<Integer>,
+        ip -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    oidc_access_tokens (id) {
+        id -> Integer,
+        token -> Text,
+        client_id -> Text,
+        user_id -> Integer,
+        scope -> Text,
+        created_at -> Timestamp,
+        expires_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    password_resets (id) {
+        id -> Integer,
+        token -> Text,
+        user_id -> Integer,
+        created_at -> Timestamp,
+        expires_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    recovery_codes (id) {
+        id -> Integer,
+        user_id -> Integer,
+        code_hash -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    sessions (id) {
+        id -> Integer,
+        token -> Text,
+        csrf_token -> Text,
+        user_id -> Integer,
+        created_at -> Timestamp,
+        expires_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    two_factor (user_id) {
+        user_id -> Integer,
+        secret -> Text,
+        enabled_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     users (id) {
         id -> Integer,
@@ -10,5 +119,29 @@ diesel::table! {
         phone -> Nullable<Double>,
         password -> Text,
         created_at -> Timestamp,
+        email_verified_at -> Nullable<Timestamp>,
     }
 }
+
+diesel::joinable!(authorization_codes -> users (user_id));
+diesel::joinable!(email_verifications -> users (user_id));
+diesel::joinable!(failed_logins -> users (user_id));
+diesel::joinable!(oidc_access_tokens -> users (user_id));
+diesel::joinable!(password_resets -> users (user_id));
+diesel::joinable!(recovery_codes -> users (user_id));
+diesel::joinable!(sessions -> users (user_id));
+diesel::joinable!(two_factor -> users (user_id));
+
+diesel::allow_tables_to_appear_in_same_query!(
+    api_keys,
+    authorization_codes,
+    email_verifications,
+    emails,
+    failed_logins,
+    oidc_access_tokens,
+    password_resets,
+    recovery_codes,
+    sessions,
+    two_factor,
+    users,
+);
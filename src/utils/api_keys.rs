@@ -0,0 +1,98 @@
+use crate::schema::api_keys;
+use crate::*;
+use chrono::{NaiveDateTime, Utc};
+use ring::digest::{digest, SHA256};
+
+use super::random_token;
+
+/// The start of every key, so leaked keys are easy to recognize
+const KEY_PREFIX: &str = "krapi_";
+
+/// The scope that opens the `/__admin` routes, like the admin token
+pub const ADMIN_SCOPE: &str = "admin";
+
+/// Hash an API key to store or look it up
+///
+/// The keys are long and random, so a fast hash is enough, unlike passwords.
+///
+/// ```
+/// use krapi::utils::hash_api_key;
+///
+/// assert_eq!(hash_api_key("krapi_abc"), hash_api_key("krapi_abc"));
+/// assert_eq!(hash_api_key("krapi_abc").len(), 64);
+/// ```
+pub fn hash_api_key(key: &str) -> String {
+    digest(&SHA256, key.as_bytes())
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Create an API key. Returns the key, which is only known now, with the stored row
+pub fn create_api_key(
+    name: &str,
+    scopes: &[String],
+    expires_at: Option<NaiveDateTime>,
+    connection: &mut SqliteConnection,
+) -> QueryResult<(String, ApiKey)> {
+    let key = format!("{}{}", KEY_PREFIX, random_token(40));
+
+    let new_key = ApiKeyNew {
+        name: name.to_string(),
+        prefix: key[..KEY_PREFIX.len() + 6].to_string(),
+        key_hash: hash_api_key(&key),
+        scopes: scopes.join(" "),
+        expires_at,
+    };
+
+    diesel::insert_into(api_keys::table)
+        .values(&new_key)
+        .execute(connection)?;
+
+    let api_key = api_keys::table
+        .filter(api_keys::key_hash.eq(&new_key.key_hash))
+        .first(connection)?;
+
+    Ok((key, api_key))
+}
+
+/// All the API keys, oldest first
+pub fn list_api_keys(connection: &mut SqliteConnection) -> QueryResult<Vec<ApiKey>> {
+    api_keys::table.order(api_keys::id.asc()).load(connection)
+}
+
+/// Delete an API key. Returns `false` if there is no key with the id
+pub fn revoke_api_key(id: i32, connection: &mut SqliteConnection) -> QueryResult<bool> {
+    let deleted = diesel::delete(api_keys::table.find(id)).execute(connection)?;
+
+    Ok(deleted > 0)
+}
+
+/// Find the API key, if it exists and hasn't expired
+pub fn find_api_key(key: &str, connection: &mut SqliteConnection) -> QueryResult<Option<ApiKey>> {
+    api_keys::table
+        .filter(api_keys::key_hash.eq(hash_api_key(key)))
+        .filter(
+            api_keys::expires_at
+                .is_null()
+                .or(api_keys::expires_at.gt(Utc::now().naive_utc())),
+        )
+        .first::<ApiKey>(connection)
+        .optional()
+}
+
+/// Find the API key, if it exists and hasn't expired, and mark it as used
+pub fn use_api_key(key: &str, connection: &mut SqliteConnection) -> QueryResult<Option<ApiKey>> {
+    let Some(mut api_key) = find_api_key(key, connection)? else {
+        return Ok(None);
+    };
+
+    let now = Utc::now().naive_utc();
+    diesel::update(api_keys::table.find(api_key.id))
+        .set(api_keys::last_used_at.eq(now))
+        .execute(connection)?;
+    api_key.last_used_at = Some(now);
+
+    Ok(Some(api_key))
+}
@@ -2,13 +2,18 @@ use crate::*;
 use diesel::r2d2::{self, ConnectionManager};
 use home::home_dir;
 
-/// Establishes a connection to the database.
+/// The path of the database file.
 ///
-/// On debug, the database is created in the working directory.
+/// If the `KRAPI_DATABASE` environment variable is set, the database is there.
+/// `krapi` sets it from `--database` or the config file.
 ///
-/// On release, the database is created in the user's home directory.
-pub fn establish_connection() -> DbPool {
-    let db_path = if cfg!(debug_assertions) {
+/// On debug, the database is in the working directory.
+///
+/// On release, the database is in the user's home directory.
+pub fn database_path() -> String {
+    if let Ok(path) = std::env::var(DATABASE_ENV) {
+        path
+    } else if cfg!(debug_assertions) {
         // On debug, use the working directory
         format!(
             "{}/{}",
@@ -22,14 +27,109 @@ pub fn establish_connection() -> DbPool {
             home_dir().unwrap().to_str().unwrap(),
             DATABASE_PATH
         )
-    };
+    }
+}
 
+/// Turns on the foreign keys of every connection of a pool
+///
+/// SQLite ignores `ON DELETE CASCADE` unless `PRAGMA foreign_keys` is on, and every new connection starts with it off.
+/// Without it, deleting a user would leave its sessions, tokens and codes behind.
+#[derive(Debug, Clone, Copy)]
+struct ForeignKeys;
+
+impl r2d2::CustomizeConnection<SqliteConnection, r2d2::Error> for ForeignKeys {
+    fn on_acquire(&self, connection: &mut SqliteConnection) -> Result<(), r2d2::Error> {
+        diesel::sql_query("PRAGMA foreign_keys = ON")
+            .execute(connection)
+            .map(|_| ())
+            .map_err(r2d2::Error::QueryError)
+    }
+}
+
+/// Establishes a connection to the database.
+///
+/// The database is created at [`database_path`].
+pub fn establish_connection() -> DbPool {
     // Sqlite connection manager
-    let manager = ConnectionManager::<SqliteConnection>::new(db_path);
+    let manager = ConnectionManager::<SqliteConnection>::new(database_path());
 
     let pool: DbPool = r2d2::Pool::builder()
+        .connection_customizer(Box::new(ForeignKeys))
         .build(manager)
         .expect("Failed to create pool.");
 
     pool
 }
+
+/// A migrated database in memory, for tests
+#[cfg(test)]
+pub fn test_connection() -> SqliteConnection {
+    let mut connection = SqliteConnection::establish(":memory:").unwrap();
+    diesel::sql_query("PRAGMA foreign_keys = ON")
+        .execute(&mut connection)
+        .unwrap();
+    super::run_migrations(&mut connection).unwrap();
+
+    connection
+}
+
+/// A pool of one connection to a migrated database in memory, for tests of routes
+///
+/// Every connection to `:memory:` is another database, so the pool keeps only one.
+#[cfg(test)]
+pub fn test_pool() -> DbPool {
+    let pool = r2d2::Pool::builder()
+        .max_size(1)
+        .connection_customizer(Box::new(ForeignKeys))
+        .build(ConnectionManager::<SqliteConnection>::new(":memory:"))
+        .unwrap();
+    super::run_migrations(&mut pool.get().unwrap()).unwrap();
+
+    pool
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{sessions, users};
+    use chrono::{Duration, Utc};
+
+    #[test]
+    fn deleting_a_user_deletes_its_sessions() {
+        let pool = test_pool();
+        let connection = &mut pool.get().unwrap();
+
+        diesel::insert_into(users::table)
+            .values(&UserNew {
+                name: "Shanto".to_string(),
+                username: "shanto".to_string(),
+                email: None,
+                img_url: None,
+                phone: None,
+                password: String::new(),
+            })
+            .execute(connection)
+            .unwrap();
+        let user_id = users::table.select(users::id).first(connection).unwrap();
+
+        diesel::insert_into(sessions::table)
+            .values(&SessionNew {
+                token: "token".to_string(),
+                csrf_token: "csrf".to_string(),
+                user_id,
+                expires_at: Utc::now().naive_utc() + Duration::days(1),
+            })
+            .execute(connection)
+            .unwrap();
+
+        diesel::delete(users::table.filter(users::id.eq(user_id)))
+            .execute(connection)
+            .unwrap();
+
+        let sessions = sessions::table
+            .count()
+            .get_result::<i64>(connection)
+            .unwrap();
+        assert_eq!(sessions, 0);
+    }
+}
@@ -0,0 +1,99 @@
+use crate::schema::users;
+use crate::*;
+use chrono::NaiveDateTime;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Formats the database can be exported to
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// A single `db.json` with a `users` key. Compatible with json-server
+    Json,
+    /// A `users.csv` file with a header row
+    Csv,
+    /// A `users.ndjson` file, with one user per line
+    Ndjson,
+}
+
+/// Export the users of the database
+///
+/// Users are exported as [`UserJson`], so the password hashes never leave the database.
+/// The other tables hold sessions, tokens and keys, which are credentials rather than data, so they are not exported.
+///
+/// If `out` is given, the files are written into that directory. Otherwise everything is written to stdout.
+pub fn export_database(
+    format: ExportFormat,
+    out: Option<&Path>,
+    db_connection: &mut SqliteConnection,
+) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+    let users = users::table
+        .order(users::id.asc())
+        .load::<User>(db_connection)?
+        .into_iter()
+        .map(UserJson::from)
+        .collect::<Vec<UserJson>>();
+
+    if let Some(out) = out {
+        fs::create_dir_all(out)?;
+    }
+
+    // Where a file should be written
+    let writer = |file_name: &str| -> io::Result<Box<dyn Write>> {
+        match out {
+            Some(out) => Ok(Box::new(File::create(out.join(file_name))?)),
+            None => Ok(Box::new(io::stdout())),
+        }
+    };
+
+    match format {
+        ExportFormat::Json => {
+            let mut writer = writer("db.json")?;
+            serde_json::to_writer_pretty(&mut writer, &serde_json::json!({ "users": users }))?;
+            writeln!(writer)?;
+        }
+        ExportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(writer("users.csv")?);
+            // `serialize` only writes the header before the first row
+            if users.is_empty() {
+                writer.write_record(&users_csv_header()?)?;
+            }
+            for user in &users {
+                writer.serialize(user)?;
+            }
+            writer.flush()?;
+        }
+        ExportFormat::Ndjson => {
+            let mut writer = writer("users.ndjson")?;
+            for user in &users {
+                serde_json::to_writer(&mut writer, user)?;
+                writeln!(writer)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The header row that `serialize` writes for [`UserJson`]
+fn users_csv_header() -> Result<csv::StringRecord, Box<dyn Error + Send + Sync + 'static>> {
+    let blank = UserJson {
+        id: 0,
+        name: String::new(),
+        username: String::new(),
+        email: None,
+        img_url: None,
+        phone: None,
+        created_at: NaiveDateTime::default(),
+        email_verified_at: None,
+    };
+
+    let mut buffer = csv::Writer::from_writer(Vec::new());
+    buffer.serialize(blank)?;
+    let bytes = buffer.into_inner()?;
+
+    Ok(csv::Reader::from_reader(bytes.as_slice())
+        .headers()?
+        .clone())
+}
@@ -1,26 +1,52 @@
 use crate::*;
+use chrono::{Duration, NaiveDateTime, NaiveTime, Utc};
 use fake::faker::internet::raw::*;
 use fake::faker::name::raw::*;
 use fake::locales::EN;
 use fake::Fake;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
 
 use super::hash::hash_password;
 
+/// How likely a signup is to happen at each hour of the day.
+///
+/// Few people sign up at night, most do during the day and in the evening.
+const HOUR_WEIGHTS: [u32; 24] = [
+    2, 1, 1, 1, 1, 2, 3, 5, 7, 8, 9, 9, 9, 9, 8, 8, 8, 9, 10, 10, 9, 7, 5, 3,
+];
+
 /// Generate users
 ///
 /// This function will generate `len` number of users and insert them into the database.
-pub async fn generate_users(len: u32, conn: DbPool) -> Result<Vec<User>, ()> {
+///
+/// The users are spread over the last `since` duration, with more recent signups being more common.
+pub async fn generate_users(len: u32, since: Duration, conn: DbPool) -> Result<Vec<User>, ()> {
+    let now = Utc::now().naive_utc();
+    let mut rng = rand::thread_rng();
+
     // Generate users
-    let generated_users = (0..len)
-        .map(|_| UserNew {
-            name: Name(EN).fake(),
-            username: Username(EN).fake(),
-            email: FreeEmail(EN).fake(),
-            password: hash_password(Password(EN, 8..16).fake::<String>()),
-            phone: None, // TODO: Add phone number
-            img_url: None,
+    let mut generated_users = (0..len)
+        .map(|_| {
+            let email: Option<String> = FreeEmail(EN).fake();
+            let created_at = random_created_at(&mut rng, since, now);
+
+            UserSeed {
+                name: Name(EN).fake(),
+                username: Username(EN).fake(),
+                // Generated users have verified their email when they signed up
+                email_verified_at: email.as_ref().map(|_| created_at),
+                email,
+                password: hash_password(Password(EN, 8..16).fake::<String>()),
+                phone: None, // TODO: Add phone number
+                img_url: None,
+                created_at,
+            }
         })
-        .collect::<Vec<UserNew>>();
+        .collect::<Vec<UserSeed>>();
+
+    // Insert the oldest first so the ids follow the signup order
+    generated_users.sort_by_key(|user| user.created_at);
 
     let mut db_connection = conn.get().unwrap();
 
@@ -58,3 +84,45 @@ pub async fn generate_users(len: u32, conn: DbPool) -> Result<Vec<User>, ()> {
         }
     }
 }
+
+/// Pick a signup time within `since` before `now`
+fn random_created_at(rng: &mut impl Rng, since: Duration, now: NaiveDateTime) -> NaiveDateTime {
+    let start = now - since;
+
+    // A growing app gets more signups every day, so later dates are more likely
+    let progress = rng.gen::<f64>().sqrt();
+    let offset = Duration::seconds((since.num_seconds() as f64 * progress) as i64);
+    let created_at = start + offset;
+
+    // For short windows the time of day doesn't matter much
+    if since < Duration::days(2) {
+        return created_at;
+    }
+
+    // Move the signup to a realistic hour of that day. On the first and the last day
+    // only the part of the day within the window can be picked
+    let midnight = created_at.date().and_time(NaiveTime::MIN);
+    let first = midnight.max(start);
+    let last = (midnight + Duration::days(1) - Duration::seconds(1)).min(now);
+
+    let hour_range = |hour: usize| {
+        let hour_start = midnight + Duration::hours(hour as i64);
+        let hour_end = hour_start + Duration::seconds(3599);
+        (hour_start.max(first), hour_end.min(last))
+    };
+
+    let weights = HOUR_WEIGHTS.iter().enumerate().map(|(hour, weight)| {
+        let (from, to) = hour_range(hour);
+        if from <= to {
+            *weight
+        } else {
+            0
+        }
+    });
+    let Ok(hours) = WeightedIndex::new(weights) else {
+        return created_at;
+    };
+
+    let (from, to) = hour_range(hours.sample(rng));
+    from + Duration::seconds(rng.gen_range(0..=(to - from).num_seconds()))
+}
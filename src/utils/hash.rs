@@ -1,16 +1,99 @@
 use argon2::{
-    password_hash::{rand_core::OsRng, PasswordHasher, SaltString},
-    Argon2,
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Algorithm, Argon2, Params, Version,
 };
+use std::sync::OnceLock;
+
+/// The cost set with [`set_hash_cost`]
+static HASH_PARAMS: OnceLock<Params> = OnceLock::new();
+
+/// How expensive hashing a password is
+///
+/// Cheap costs keep tests and seeding fast, strong costs show what a real server would do.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HashCost {
+    /// Memory in KiB [default: 19456]
+    pub memory: u32,
+    /// Passes over the memory [default: 2]
+    pub iterations: u32,
+}
+
+impl Default for HashCost {
+    fn default() -> Self {
+        Self {
+            memory: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+        }
+    }
+}
+
+/// Set the cost of the new hashes. Can only be set once, before anything is hashed
+pub fn set_hash_cost(cost: HashCost) -> Result<(), String> {
+    let params = Params::new(cost.memory, cost.iterations, Params::DEFAULT_P_COST, None)
+        .map_err(|e| format!("Invalid hash cost: {}", e))?;
+
+    HASH_PARAMS
+        .set(params)
+        .map_err(|_| "The hash cost is already set".to_string())
+}
+
+/// Argon2id with the current cost
+fn argon2() -> Argon2<'static> {
+    let params = HASH_PARAMS.get().cloned().unwrap_or_default();
+
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+}
 
 /// Hash the password
 pub fn hash_password(password: impl AsRef<str>) -> String {
-    let argon2 = Argon2::default();
     let salt = SaltString::generate(&mut OsRng);
 
     // hashed password
-    argon2
+    argon2()
         .hash_password(password.as_ref().as_bytes(), &salt)
         .unwrap()
         .to_string()
 }
+
+/// Check the password against a hash from [`hash_password`]
+///
+/// The cost stored in the hash is used, so hashes made with another cost still match.
+///
+/// ```
+/// use krapi::utils::hash::{hash_password, verify_password};
+///
+/// let hash = hash_password("admin005");
+///
+/// assert!(verify_password("admin005", &hash));
+/// assert!(!verify_password("admin006", &hash));
+/// assert!(!verify_password("admin005", "not a hash"));
+/// ```
+pub fn verify_password(password: impl AsRef<str>, hash: &str) -> bool {
+    let Ok(hash) = PasswordHash::new(hash) else {
+        return false;
+    };
+
+    argon2()
+        .verify_password(password.as_ref().as_bytes(), &hash)
+        .is_ok()
+}
+
+/// Whether the hash was made with another algorithm or cost than the current one
+///
+/// Such a hash should be made again the next time the password is known, like on a login.
+pub fn needs_rehash(hash: &str) -> bool {
+    let Ok(hash) = PasswordHash::new(hash) else {
+        return false;
+    };
+    let Ok(params) = Params::try_from(&hash) else {
+        return true;
+    };
+    let current = argon2();
+    let current = current.params();
+
+    hash.algorithm != Algorithm::Argon2id.ident()
+        || hash.version != Some(Version::V0x13.into())
+        || params.m_cost() != current.m_cost()
+        || params.t_cost() != current.t_cost()
+        || params.p_cost() != current.p_cost()
+}
@@ -0,0 +1,253 @@
+use crate::schema::users;
+use crate::*;
+use argon2::password_hash::PasswordHash;
+use chrono::{NaiveDateTime, Timelike, Utc};
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::path::Path;
+
+use super::hash::hash_password;
+
+/// Formats of the fixture files
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// An array of users, or an object with a `users` array
+    Json,
+    /// A header row followed by one user per row
+    Csv,
+}
+
+impl ImportFormat {
+    /// Guess the format from the file extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// A user of a fixture file
+///
+/// It needs either a plaintext `password` or a `password_hash`, which is an Argon2 hash in the PHC format,
+/// like the hashes of the database.
+///
+/// A user with an email is verified from the time of the import, unless `email_verified` is `false`
+/// or `email_verified_at` gives another time, so `--require-verified-email` doesn't refuse the fixtures.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ImportUser {
+    pub name: String,
+    pub username: String,
+    pub email: Option<String>,
+    pub img_url: Option<String>,
+    pub phone: Option<f64>,
+    pub password: Option<String>,
+    pub password_hash: Option<String>,
+    pub email_verified: Option<bool>,
+    pub email_verified_at: Option<NaiveDateTime>,
+}
+
+/// A row of the fixture file: the row number with either the user or the reason it can't be used
+pub type ImportRow = (usize, Result<ImportUser, String>);
+
+/// A row of the fixture file that can't be imported
+#[derive(Debug, Clone)]
+pub struct RowError {
+    /// The row number, starting from 1. For CSV files this is the line number.
+    pub row: usize,
+    pub msg: String,
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Row {}: {}", self.row, self.msg)
+    }
+}
+
+/// Read the users from a fixture file
+///
+/// Every row is checked against [`ImportUser`]. Returns the row number with either the user or the reason the row can't be used.
+pub fn read_users(
+    path: &Path,
+    format: ImportFormat,
+) -> Result<Vec<ImportRow>, Box<dyn Error + Send + Sync + 'static>> {
+    let file = File::open(path)?;
+
+    let rows = match format {
+        ImportFormat::Json => {
+            let json: serde_json::Value = serde_json::from_reader(file)?;
+
+            // Accept both `[...]` and `{ "users": [...] }`
+            let rows = match json {
+                serde_json::Value::Array(rows) => rows,
+                serde_json::Value::Object(mut object) => match object.remove("users") {
+                    Some(serde_json::Value::Array(rows)) => rows,
+                    _ => return Err("Expected a `users` array in the JSON object".into()),
+                },
+                _ => return Err("Expected a JSON array of users".into()),
+            };
+
+            rows.into_iter()
+                .enumerate()
+                .map(|(i, row)| {
+                    let user = serde_json::from_value::<ImportUser>(row).map_err(|e| e.to_string());
+                    (i + 1, user)
+                })
+                .collect::<Vec<_>>()
+        }
+        ImportFormat::Csv => csv::Reader::from_reader(file)
+            .deserialize::<ImportUser>()
+            .enumerate()
+            .map(|(i, row)| {
+                // +1 for the header and +1 because lines start from 1
+                (i + 2, row.map_err(|e| e.to_string()))
+            })
+            .collect::<Vec<_>>(),
+    };
+
+    Ok(rows)
+}
+
+/// Validate the users and insert them into the database
+///
+/// The `password` is hashed with [`hash_password`]. A `password_hash` is kept as it is.
+///
+/// The users are inserted in a single transaction. If any row is invalid, nothing is inserted and all the errors are returned.
+pub fn import_users(
+    rows: Vec<ImportRow>,
+    db_connection: &mut SqliteConnection,
+) -> Result<Result<usize, Vec<RowError>>, diesel::result::Error> {
+    db_connection.transaction(|db_connection| {
+        let mut errors = Vec::new();
+        let mut new_users = Vec::new();
+
+        // usernames and emails seen so far in the file
+        let mut usernames = HashSet::new();
+        let mut emails = HashSet::new();
+
+        for (row, user) in rows {
+            let user = match user {
+                Ok(user) => user,
+                Err(msg) => {
+                    errors.push(RowError { row, msg });
+                    continue;
+                }
+            };
+
+            if let Some(msg) = validate_user(&user) {
+                errors.push(RowError { row, msg });
+                continue;
+            }
+
+            // Check for duplicates in the file
+            let duplicate_email = user
+                .email
+                .as_ref()
+                .map(|email| !emails.insert(email.clone()))
+                .unwrap_or(false);
+
+            if !usernames.insert(user.username.clone()) || duplicate_email {
+                errors.push(RowError {
+                    row,
+                    msg: "Username or email is repeated in the file".to_string(),
+                });
+                continue;
+            }
+
+            // Check for duplicates in the database
+            let exists = users::table
+                .filter(users::username.eq(&user.username))
+                .or_filter(users::email.eq(&user.email))
+                .first::<User>(db_connection)
+                .optional()?
+                .is_some();
+
+            if exists {
+                errors.push(RowError {
+                    row,
+                    msg: "Username or email already exists".to_string(),
+                });
+                continue;
+            }
+
+            new_users.push(user);
+        }
+
+        if !errors.is_empty() {
+            return Ok(Err(errors));
+        }
+
+        // Whole seconds, like the `CURRENT_TIMESTAMP` default of `created_at`
+        let now = Utc::now().naive_utc().with_nanosecond(0).unwrap();
+        let new_users = new_users
+            .into_iter()
+            .map(|user| UserSeed {
+                email_verified_at: match (user.email_verified, user.email_verified_at) {
+                    (_, Some(verified_at)) => Some(verified_at),
+                    (Some(false), None) => None,
+                    _ => user.email.as_ref().map(|_| now),
+                },
+                created_at: now,
+                name: user.name,
+                username: user.username,
+                email: user.email,
+                img_url: user.img_url,
+                phone: user.phone,
+                // `validate_user` checked that one of them is there
+                password: match (user.password_hash, user.password) {
+                    (Some(hash), _) => hash,
+                    (None, password) => hash_password(password.unwrap_or_default()),
+                },
+            })
+            .collect::<Vec<UserSeed>>();
+
+        let inserted = diesel::insert_into(users::table)
+            .values(&new_users)
+            .execute(db_connection)?;
+
+        Ok(Ok(inserted))
+    })
+}
+
+/// Check the fields that serde can't check
+fn validate_user(user: &ImportUser) -> Option<String> {
+    if user.name.trim().is_empty() {
+        return Some("`name` can't be empty".to_string());
+    }
+
+    if user.username.trim().is_empty() {
+        return Some("`username` can't be empty".to_string());
+    }
+
+    match (&user.password, &user.password_hash) {
+        (Some(_), Some(_)) | (None, None) => {
+            Some("Give either `password` or `password_hash`".to_string())
+        }
+        (Some(password), None) if password.is_empty() => {
+            Some("`password` can't be empty".to_string())
+        }
+        (None, Some(hash)) if !is_argon2_hash(hash) => Some(
+            "`password_hash` has to be an Argon2 hash, like `$argon2id$v=19$m=19456,t=2,p=1$...`"
+                .to_string(),
+        ),
+        _ => match (user.email_verified, user.email_verified_at) {
+            (Some(false), Some(_)) => {
+                Some("`email_verified` is false but `email_verified_at` is given".to_string())
+            }
+            (Some(true), _) | (_, Some(_)) if user.email.is_none() => {
+                Some("Only a user with an `email` can be verified".to_string())
+            }
+            _ => None,
+        },
+    }
+}
+
+/// Whether the hash is an Argon2 hash in the PHC format, which [`verify_password`](super::hash::verify_password) can check
+fn is_argon2_hash(hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .map(|hash| hash.algorithm.as_str().starts_with("argon2"))
+        .unwrap_or(false)
+}
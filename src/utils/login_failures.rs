@@ -0,0 +1,257 @@
+use crate::schema::failed_logins;
+use crate::*;
+use chrono::{Duration, NaiveDateTime, Utc};
+
+/// When to lock an account after failed logins
+///
+/// By default an account is locked after 5 failed logins within 15 minutes, and IPs are never locked.
+#[derive(Clone, Debug)]
+pub struct Lockout {
+    /// How many failed logins of a user within `period` lock the account. `0` never locks it
+    pub attempts: u32,
+    /// How many failed logins from an IP within `period` lock every login from it. `0` never locks it
+    ///
+    /// Every local client has the same IP, so this is separate from `attempts`.
+    pub ip_attempts: u32,
+    /// How long the account stays locked after the last failed login
+    pub period: Duration,
+}
+
+impl Default for Lockout {
+    fn default() -> Self {
+        Self {
+            attempts: 5,
+            ip_attempts: 0,
+            period: Duration::minutes(15),
+        }
+    }
+}
+
+/// Whether a login can be tried
+#[derive(Debug, Clone, Copy)]
+pub enum LoginLock {
+    Open(LoginAttemptsJson),
+    Locked(LockoutJson),
+}
+
+/// Whose failed logins are counted
+enum FailureKey<'a> {
+    User(i32),
+    Ip(&'a str),
+}
+
+/// Record a failed login of a user, or of a username that doesn't exist
+pub fn record_login_failure(
+    user_id: Option<i32>,
+    ip: &str,
+    connection: &mut SqliteConnection,
+) -> QueryResult<()> {
+    let failure = FailedLoginNew {
+        user_id,
+        ip: ip.to_string(),
+        created_at: Utc::now().naive_utc(),
+    };
+
+    diesel::insert_into(failed_logins::table)
+        .values(&failure)
+        .execute(connection)?;
+
+    Ok(())
+}
+
+/// Forget the failed logins of a user, after a successful login or a password reset
+pub fn clear_login_failures(user_id: i32, connection: &mut SqliteConnection) -> QueryResult<()> {
+    diesel::delete(failed_logins::table.filter(failed_logins::user_id.eq(user_id)))
+        .execute(connection)?;
+
+    Ok(())
+}
+
+/// Check the failed logins of the user, and of the IP if [`Lockout::ip_attempts`] is set
+///
+/// Each is locked when it reaches its number of failed logins within [`Lockout::period`],
+/// until a period after the last one. Locking the IP stops guessing many usernames.
+pub fn login_lock(
+    user_id: Option<i32>,
+    ip: &str,
+    lockout: &Lockout,
+    connection: &mut SqliteConnection,
+) -> QueryResult<LoginLock> {
+    let now = Utc::now().naive_utc();
+
+    // The failures of the user are counted even when they don't lock it, to show a captcha
+    let mut keys = Vec::new();
+    if let Some(user_id) = user_id {
+        keys.push((FailureKey::User(user_id), lockout.attempts));
+    }
+    if lockout.ip_attempts > 0 {
+        keys.push((FailureKey::Ip(ip), lockout.ip_attempts));
+    }
+
+    let mut failed_attempts = 0;
+    let mut remaining_attempts: Option<u32> = None;
+    let mut locked_until: Option<NaiveDateTime> = None;
+
+    for (key, attempts) in keys {
+        let failures = || {
+            let query = failed_logins::table.into_boxed();
+
+            match key {
+                FailureKey::User(user_id) => query.filter(failed_logins::user_id.eq(user_id)),
+                FailureKey::Ip(ip) => query.filter(failed_logins::ip.eq(ip)),
+            }
+        };
+
+        let last = failures()
+            .select(diesel::dsl::max(failed_logins::created_at))
+            .first::<Option<NaiveDateTime>>(connection)?;
+
+        if let Some(last) = last {
+            // The failures that came in a row before the last one
+            let in_a_row = failures()
+                .filter(failed_logins::created_at.gt(last - lockout.period))
+                .count()
+                .get_result::<i64>(connection)? as u32;
+
+            if attempts > 0 && in_a_row >= attempts && now < last + lockout.period {
+                let until = last + lockout.period;
+                locked_until =
+                    Some(locked_until.map_or(until, |locked_until| locked_until.max(until)));
+                continue;
+            }
+        }
+
+        let recent = failures()
+            .filter(failed_logins::created_at.gt(now - lockout.period))
+            .count()
+            .get_result::<i64>(connection)? as u32;
+        failed_attempts = failed_attempts.max(recent);
+
+        if attempts > 0 {
+            let remaining = attempts.saturating_sub(recent);
+            remaining_attempts = Some(remaining_attempts.map_or(remaining, |r| r.min(remaining)));
+        }
+    }
+
+    if let Some(locked_until) = locked_until {
+        return Ok(LoginLock::Locked(LockoutJson {
+            locked_until,
+            // Rounded up, so retrying after that many seconds works
+            retry_after: ((locked_until - now).num_milliseconds() + 999) / 1000,
+        }));
+    }
+
+    Ok(LoginLock::Open(LoginAttemptsJson {
+        failed_attempts,
+        remaining_attempts,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_connection;
+
+    fn add_user(username: &str, connection: &mut SqliteConnection) -> i32 {
+        diesel::insert_into(crate::schema::users::table)
+            .values(&UserNew {
+                name: username.to_string(),
+                username: username.to_string(),
+                email: None,
+                img_url: None,
+                phone: None,
+                password: String::new(),
+            })
+            .execute(connection)
+            .unwrap();
+
+        crate::schema::users::table
+            .select(crate::schema::users::id)
+            .order(crate::schema::users::id.desc())
+            .first(connection)
+            .unwrap()
+    }
+
+    fn fail(times: u32, user_id: Option<i32>, ip: &str, connection: &mut SqliteConnection) {
+        for _ in 0..times {
+            record_login_failure(user_id, ip, connection).unwrap();
+        }
+    }
+
+    #[test]
+    fn locks_only_the_account() {
+        let connection = &mut test_connection();
+        let lockout = Lockout {
+            attempts: 3,
+            ..Lockout::default()
+        };
+
+        let shanto = add_user("shanto", connection);
+        let other = add_user("other", connection);
+
+        fail(3, Some(shanto), "127.0.0.1", connection);
+
+        let lock = login_lock(Some(shanto), "127.0.0.1", &lockout, connection).unwrap();
+        assert!(matches!(lock, LoginLock::Locked(_)));
+
+        // Another account from the same IP can still log in
+        let lock = login_lock(Some(other), "127.0.0.1", &lockout, connection).unwrap();
+        assert!(matches!(
+            lock,
+            LoginLock::Open(LoginAttemptsJson {
+                failed_attempts: 0,
+                remaining_attempts: Some(3),
+            })
+        ));
+    }
+
+    #[test]
+    fn locks_the_ip_when_asked() {
+        let connection = &mut test_connection();
+        let lockout = Lockout {
+            ip_attempts: 3,
+            ..Lockout::default()
+        };
+
+        fail(3, None, "10.0.0.7", connection);
+
+        let lock = login_lock(Some(2), "10.0.0.7", &lockout, connection).unwrap();
+        assert!(matches!(lock, LoginLock::Locked(_)));
+
+        let lock = login_lock(Some(2), "10.0.0.8", &lockout, connection).unwrap();
+        assert!(matches!(lock, LoginLock::Open(_)));
+    }
+
+    #[test]
+    fn locks_by_default() {
+        let connection = &mut test_connection();
+        let shanto = add_user("shanto", connection);
+
+        fail(5, Some(shanto), "127.0.0.1", connection);
+
+        let lock = login_lock(Some(shanto), "127.0.0.1", &Lockout::default(), connection).unwrap();
+        assert!(matches!(lock, LoginLock::Locked(_)));
+    }
+
+    #[test]
+    fn never_locks_with_zero_attempts() {
+        let connection = &mut test_connection();
+        let lockout = Lockout {
+            attempts: 0,
+            ..Lockout::default()
+        };
+
+        let shanto = add_user("shanto", connection);
+
+        fail(10, Some(shanto), "127.0.0.1", connection);
+
+        let lock = login_lock(Some(shanto), "127.0.0.1", &lockout, connection).unwrap();
+        assert!(matches!(
+            lock,
+            LoginLock::Open(LoginAttemptsJson {
+                failed_attempts: 10,
+                remaining_attempts: None,
+            })
+        ));
+    }
+}
@@ -1,11 +1,55 @@
+mod api_keys;
 mod establish_connection;
+mod export_database;
 mod generate_users;
 pub mod hash;
+mod import_users;
+mod login_failures;
+mod oidc;
+mod openapi;
+mod parse_duration;
+mod print_docs;
+mod random_token;
 pub mod response;
 mod run_migrations;
+mod seed;
+mod send_email;
 mod server_error;
+mod snapshot;
+mod tls;
+mod truncate_tables;
+mod two_factor;
+mod typescript;
 
-pub use establish_connection::establish_connection;
+pub use api_keys::{
+    create_api_key, find_api_key, hash_api_key, list_api_keys, revoke_api_key, use_api_key,
+    ADMIN_SCOPE,
+};
+pub use establish_connection::{database_path, establish_connection};
+#[cfg(test)]
+pub(crate) use establish_connection::{test_connection, test_pool};
+pub use export_database::{export_database, ExportFormat};
 pub use generate_users::generate_users;
-pub use run_migrations::run_migrations;
+pub use import_users::{import_users, read_users, ImportFormat, ImportRow, ImportUser, RowError};
+pub use login_failures::{
+    clear_login_failures, login_lock, record_login_failure, Lockout, LoginLock,
+};
+pub use oidc::{check_code_verifier, SigningKey};
+pub use openapi::openapi_spec;
+pub use parse_duration::parse_duration;
+pub use print_docs::print_docs;
+pub use random_token::random_token;
+pub use run_migrations::{rerun_migrations, run_migrations};
+pub use seed::{seed_database, Preset};
+pub use send_email::send_email;
 pub use server_error::server_error;
+pub use snapshot::{
+    is_valid_snapshot_name, list_snapshots, restore_snapshot, save_snapshot, snapshot_exists,
+    snapshots_dir, Snapshot,
+};
+pub use tls::{ca_path, local_certificate, rustls_config, tls_dir, TlsFiles};
+pub use truncate_tables::truncate_tables;
+pub use two_factor::{
+    check_totp_code, generate_recovery_codes, generate_totp_secret, qr_svg, totp,
+};
+pub use typescript::typescript_definitions;
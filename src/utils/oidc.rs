@@ -0,0 +1,96 @@
+use crate::*;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ring::digest::{digest, SHA256};
+use ring::rand::SystemRandom;
+use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
+use std::error::Error;
+
+/// The key that signs the ID tokens, with ES256
+///
+/// A new key is generated every time the server starts, so ID tokens from before a restart
+/// can't be checked anymore. Clients fetch the key again from the JWKS endpoint.
+pub struct SigningKey {
+    key_pair: EcdsaKeyPair,
+    rng: SystemRandom,
+    /// Identifies the key in the JWKS and in the header of the tokens
+    pub kid: String,
+}
+
+impl SigningKey {
+    /// Generate a new P-256 key
+    pub fn generate() -> Result<Self, Box<dyn Error + Send + Sync + 'static>> {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng)
+            .map_err(|_| "Could not generate the signing key")?;
+        let key_pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref())
+            .map_err(|e| e.to_string())?;
+
+        let fingerprint = digest(&SHA256, key_pair.public_key().as_ref());
+        let kid = URL_SAFE_NO_PAD.encode(&fingerprint.as_ref()[..8]);
+
+        Ok(Self { key_pair, rng, kid })
+    }
+
+    /// The public key as a JWK
+    pub fn jwk(&self) -> Jwk {
+        // Uncompressed: `0x04`, then the x and the y coordinates, 32 bytes each
+        let point = self.key_pair.public_key().as_ref();
+
+        Jwk {
+            kty: "EC".to_string(),
+            crv: "P-256".to_string(),
+            x: URL_SAFE_NO_PAD.encode(&point[1..33]),
+            y: URL_SAFE_NO_PAD.encode(&point[33..65]),
+            kid: self.kid.clone(),
+            key_use: "sig".to_string(),
+            alg: "ES256".to_string(),
+        }
+    }
+
+    /// Sign the claims into a JWT
+    pub fn sign(
+        &self,
+        claims: &impl Serialize,
+    ) -> Result<String, Box<dyn Error + Send + Sync + 'static>> {
+        let header = serde_json::json!({ "alg": "ES256", "typ": "JWT", "kid": self.kid });
+
+        let signing_input = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(serde_json::to_vec(&header)?),
+            URL_SAFE_NO_PAD.encode(serde_json::to_vec(claims)?)
+        );
+
+        let signature = self
+            .key_pair
+            .sign(&self.rng, signing_input.as_bytes())
+            .map_err(|_| "Could not sign the token")?;
+
+        Ok(format!(
+            "{}.{}",
+            signing_input,
+            URL_SAFE_NO_PAD.encode(signature.as_ref())
+        ))
+    }
+}
+
+/// Check a PKCE `code_verifier` against the `code_challenge` of the authorization request
+///
+/// ```
+/// use krapi::utils::check_code_verifier;
+///
+/// // The example of RFC 7636
+/// let verifier = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+/// let challenge = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+///
+/// assert!(check_code_verifier(verifier, challenge, "S256"));
+/// assert!(!check_code_verifier("another verifier", challenge, "S256"));
+/// assert!(check_code_verifier(verifier, verifier, "plain"));
+/// ```
+pub fn check_code_verifier(verifier: &str, challenge: &str, method: &str) -> bool {
+    match method {
+        "S256" => URL_SAFE_NO_PAD.encode(digest(&SHA256, verifier.as_bytes())) == challenge,
+        "plain" => verifier == challenge,
+        _ => false,
+    }
+}
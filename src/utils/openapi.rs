@@ -0,0 +1,175 @@
+use crate::routes::ROUTES;
+use crate::*;
+use schemars::gen::SchemaSettings;
+use serde_json::{json, Map, Value};
+
+/// Build the OpenAPI 3 description of all the [`ROUTES`]
+///
+/// `server_url` is the address the server is reachable at, like `http://localhost:8090`.
+pub fn openapi_spec(server_url: &str) -> Value {
+    let mut gen = SchemaSettings::openapi3().into_generator();
+
+    // The envelope every JSON response is wrapped in
+    let response_schema = gen.subschema_for::<Response>();
+
+    let mut paths = Map::new();
+
+    for route in ROUTES {
+        let mut operation = Map::new();
+
+        operation.insert("operationId".into(), json!(route.id));
+        operation.insert("tags".into(), json!([format!("{:?}", route.resource)]));
+        operation.insert("summary".into(), json!(route.summary));
+        operation.insert("description".into(), json!(route.description));
+
+        // Path and query parameters and headers
+        let parameters = route
+            .params
+            .iter()
+            .map(|param| (param, "path"))
+            .chain(route.query.iter().map(|param| (param, "query")))
+            .chain(route.headers.iter().map(|header| (header, "header")))
+            .map(|(param, location)| {
+                // Numeric examples, like the id of a user, are integers
+                let (kind, example) = match param.example.parse::<i64>() {
+                    Ok(number) => ("integer", json!(number)),
+                    Err(_) => ("string", json!(param.example)),
+                };
+
+                json!({
+                    "name": param.name,
+                    "in": location,
+                    "required": true,
+                    "description": param.description,
+                    "schema": { "type": kind },
+                    "example": example,
+                })
+            })
+            .collect::<Vec<Value>>();
+
+        if !parameters.is_empty() {
+            operation.insert("parameters".into(), json!(parameters));
+        }
+
+        if let Some(body_schema) = route.body_schema {
+            let mut content = json!({ "schema": body_schema(&mut gen) });
+
+            let (content_type, example) = if route.form {
+                (
+                    "application/x-www-form-urlencoded",
+                    route.example_body.and_then(parse_form_example),
+                )
+            } else {
+                (
+                    "application/json",
+                    route.example_body.and_then(parse_example),
+                )
+            };
+
+            if let Some(example) = example {
+                content["example"] = example;
+            }
+
+            operation.insert(
+                "requestBody".into(),
+                json!({
+                    "required": true,
+                    "content": { content_type: content },
+                }),
+            );
+        }
+
+        let response = if route.returns.is_empty() {
+            // Routes that don't use the envelope
+            match parse_example(route.example_response) {
+                Some(example) => json!({
+                    "description": route.summary,
+                    "content": {
+                        "application/json": { "schema": { "type": "object" }, "example": example }
+                    },
+                }),
+                None => {
+                    let content_type = if route.example_response.starts_with('<') {
+                        "text/html"
+                    } else {
+                        "text/plain"
+                    };
+
+                    json!({
+                        "description": route.summary,
+                        "content": {
+                            content_type: { "schema": { "type": "string" }, "example": route.example_response }
+                        },
+                    })
+                }
+            }
+        } else {
+            let response_types = route
+                .returns
+                .iter()
+                .map(|returns| returns.response_type)
+                .collect::<Vec<ResponseType>>();
+
+            let description = route
+                .returns
+                .iter()
+                .map(|returns| format!("- `{:?}` {}", returns.response_type, returns.description))
+                .collect::<Vec<String>>()
+                .join("\n");
+
+            let mut properties = json!({ "type": { "enum": response_types } });
+            if let Some(data_schema) = route.data_schema {
+                properties["data"] = json!(data_schema(&mut gen));
+            }
+
+            let mut content = json!({
+                "schema": {
+                    "allOf": [
+                        response_schema,
+                        { "type": "object", "properties": properties },
+                    ]
+                }
+            });
+
+            if let Some(example) = parse_example(route.example_response) {
+                content["example"] = example;
+            }
+
+            json!({
+                "description": description,
+                "content": { "application/json": content },
+            })
+        };
+
+        operation.insert("responses".into(), json!({ "200": response }));
+
+        // Routes with the same path share the path item
+        let path_item = paths
+            .entry(route.path)
+            .or_insert_with(|| Value::Object(Map::new()));
+        path_item[route.method.to_lowercase()] = Value::Object(operation);
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "KR Api",
+            "description": "A local api server for frontend applications.",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "servers": [{ "url": server_url }],
+        "paths": paths,
+        "components": { "schemas": gen.definitions() },
+    })
+}
+
+fn parse_example(example: &str) -> Option<Value> {
+    serde_json::from_str(example).ok()
+}
+
+/// A form example, like `a=1&b=2`, as the object OpenAPI expects
+fn parse_form_example(example: &str) -> Option<Value> {
+    serde_urlencoded::from_str::<Vec<(String, String)>>(example)
+        .ok()
+        .map(|fields| Value::Object(fields.into_iter().map(|(k, v)| (k, json!(v))).collect()))
+}
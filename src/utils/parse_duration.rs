@@ -0,0 +1,71 @@
+use chrono::Duration;
+
+/// The longest duration, 1000 years in milliseconds
+const MAX_MS: i64 = 1000 * 365 * 24 * 60 * 60 * 1000;
+
+/// Parse a human friendly duration
+///
+/// The value is a number followed by a unit. Supported units are:
+///
+/// - `ms` milliseconds
+/// - `s` seconds
+/// - `m` minutes
+/// - `h` hours
+/// - `d` days
+/// - `w` weeks
+/// - `mo` months (30 days)
+/// - `y` years (365 days)
+///
+/// Durations longer than 1000 years are rejected, so adding them to a date can't overflow.
+///
+/// ## Example
+///
+/// ```
+/// # use krapi::utils::parse_duration;
+/// # use chrono::Duration;
+/// #
+/// assert_eq!(parse_duration("2y").unwrap(), Duration::days(730));
+/// assert_eq!(parse_duration("90m").unwrap(), Duration::minutes(90));
+/// assert!(parse_duration("soon").is_err());
+/// assert!(parse_duration("999999999999999d").is_err());
+/// ```
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+
+    // split the number from the unit
+    let unit_start = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(unit_start);
+
+    let amount: i64 = amount
+        .parse()
+        .map_err(|_| format!("Invalid duration `{}`. Expected something like `2y`", value))?;
+
+    // how many milliseconds one unit is
+    let unit_ms: i64 = match unit.trim() {
+        "ms" => 1,
+        "s" => 1000,
+        "m" => 60 * 1000,
+        "h" => 60 * 60 * 1000,
+        "d" => 24 * 60 * 60 * 1000,
+        "w" => 7 * 24 * 60 * 60 * 1000,
+        "mo" => 30 * 24 * 60 * 60 * 1000,
+        "y" => 365 * 24 * 60 * 60 * 1000,
+        "" => return Err(format!("Missing unit in duration `{}`", value)),
+        unit => {
+            return Err(format!(
+                "Unknown unit `{}`. Use one of: ms, s, m, h, d, w, mo, y",
+                unit
+            ))
+        }
+    };
+
+    let duration = amount
+        .checked_mul(unit_ms)
+        .filter(|ms| *ms <= MAX_MS)
+        .map(Duration::milliseconds)
+        .ok_or_else(|| format!("The duration `{}` is too large. The most is 1000y", value))?;
+
+    Ok(duration)
+}
@@ -0,0 +1,114 @@
+use crate::routes::registry::routes_of;
+use crate::*;
+
+/// Print the documentation of all the routes of a resource
+pub fn print_docs(resource: Resource) {
+    let title = format!("{:?}", resource);
+    println!("{}\n{}\n", title, "=".repeat(title.len()));
+
+    for route in routes_of(resource) {
+        print_route(route);
+    }
+}
+
+fn print_route(route: &RouteInfo) {
+    println!("{} {}", route.method, route.path);
+    println!("  {}\n", route.summary);
+    println!("{}\n", indent(route.description, 2));
+
+    if !route.params.is_empty() {
+        println!("  Path parameters:");
+        for param in route.params {
+            println!("    {:<20} {}", param.name, param.description);
+        }
+        println!();
+    }
+
+    if !route.query.is_empty() {
+        println!("  Query parameters:");
+        for param in route.query {
+            println!("    {:<20} {}", param.name, param.description);
+        }
+        println!();
+    }
+
+    if !route.headers.is_empty() {
+        println!("  Headers:");
+        for header in route.headers {
+            println!("    {:<20} {}", header.name, header.description);
+        }
+        println!();
+    }
+
+    if let Some(body) = route.body {
+        println!("  Body:");
+        println!("{}\n", indent(body, 4));
+    }
+
+    if !route.returns.is_empty() {
+        println!("  Returns:");
+        for returns in route.returns {
+            println!(
+                "    {:<20} {}",
+                format!("{:?}", returns.response_type),
+                returns.description
+            );
+        }
+        println!();
+    }
+
+    println!("  Example request:");
+    println!("{}\n", indent(&example_request(route), 4));
+
+    println!("  Example response:");
+    println!("{}\n", indent(route.example_response, 4));
+}
+
+/// A curl command that calls the route with the example values
+fn example_request(route: &RouteInfo) -> String {
+    let mut path = route.path.to_string();
+    for param in route.params {
+        path = path.replace(&format!("{{{}}}", param.name), param.example);
+    }
+
+    let mut url = format!("http://localhost:{}{}", DEFAULT_PORT, path);
+
+    let query = route
+        .query
+        .iter()
+        .map(|param| format!("{}={}", param.name, param.example))
+        .collect::<Vec<String>>();
+    if !query.is_empty() {
+        // Quoted, so the shell doesn't read `&`
+        url = format!("\"{}?{}\"", url, query.join("&"));
+    }
+
+    let mut command = String::from("curl");
+    if route.method != "GET" {
+        command.push_str(&format!(" -X {}", route.method));
+    }
+    command.push_str(&format!(" {}", url));
+
+    for header in route.headers {
+        command.push_str(&format!(
+            " \\\n  -H \"{}: {}\"",
+            header.name, header.example
+        ));
+    }
+
+    if let Some(body) = route.example_body {
+        if !route.form {
+            command.push_str(" \\\n  -H \"Content-Type: application/json\"");
+        }
+        command.push_str(&format!(" \\\n  -d '{}'", body));
+    }
+
+    command
+}
+
+fn indent(text: &str, spaces: usize) -> String {
+    text.lines()
+        .map(|line| format!("{}{}", " ".repeat(spaces), line))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
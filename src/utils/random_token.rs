@@ -0,0 +1,15 @@
+use rand::distributions::{Alphanumeric, DistString};
+
+/// Generate a random token of letters and digits
+///
+/// ## Example
+///
+/// ```
+/// # use krapi::utils::random_token;
+/// #
+/// let token = random_token(32);
+/// assert_eq!(token.len(), 32);
+/// ```
+pub fn random_token(len: usize) -> String {
+    Alphanumeric.sample_string(&mut rand::thread_rng(), len)
+}
@@ -1,3 +1,4 @@
+use actix_web::http::StatusCode;
 use actix_web::HttpResponse;
 
 use crate::*;
@@ -5,7 +6,7 @@ use crate::*;
 /// JSON response types
 ///
 /// These are the types of responses that the server can send to the client.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
 #[non_exhaustive]
 pub enum ResponseType {
     /// The resource already exists
@@ -24,6 +25,12 @@ pub enum ResponseType {
     Unauthorized,
     /// The password is incorrect
     IncorrectPassword,
+    /// The client sent too many requests
+    TooManyRequests,
+    /// The password is correct, but the code from the authenticator app is needed too
+    TwoFactorRequired,
+    /// Too many logins failed. The account is locked until the time in the data
+    AccountLocked,
 }
 
 /// The response struct that is sent to the client
@@ -37,7 +44,7 @@ pub enum ResponseType {
 /// Build a response and send it to the client:
 ///
 /// ```
-/// # use krapi::Response;
+/// # use krapi::utils::response::Response;
 /// # use serde::{Deserialize, Serialize};
 /// #
 /// #[derive(Serialize, Deserialize, Debug)]
@@ -69,7 +76,8 @@ pub enum ResponseType {
 ///    }
 /// }
 /// ```
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+#[schemars(description = "The envelope every JSON response is wrapped in")]
 pub struct Response {
     #[serde(rename = "type")]
     response_type: ResponseType,
@@ -159,6 +167,33 @@ impl Response {
         }
     }
 
+    /// Set the type to [`ResponseType::TooManyRequests`]
+    pub fn too_many_requests() -> Self {
+        Self {
+            response_type: ResponseType::TooManyRequests,
+            msg: None,
+            data: None,
+        }
+    }
+
+    /// Set the type to [`ResponseType::TwoFactorRequired`]
+    pub fn two_factor_required() -> Self {
+        Self {
+            response_type: ResponseType::TwoFactorRequired,
+            msg: None,
+            data: None,
+        }
+    }
+
+    /// Set the type to [`ResponseType::AccountLocked`]
+    pub fn account_locked() -> Self {
+        Self {
+            response_type: ResponseType::AccountLocked,
+            msg: None,
+            data: None,
+        }
+    }
+
     /// Set a custom message
     pub fn msg(mut self, message: &str) -> Self {
         self.msg = Some(message.to_string());
@@ -179,7 +214,7 @@ impl Response {
     ///
     /// ## Example
     /// ```
-    /// # use krapi::Response;
+    /// # use krapi::utils::response::Response;
     /// # use serde::{Deserialize, Serialize};
     /// # use actix_web::HttpResponse;
     /// # use actix_web::get;    
@@ -204,4 +239,11 @@ impl Response {
     pub fn send(&self) -> HttpResponse {
         HttpResponse::Ok().json(self)
     }
+
+    /// Send the response to the client with a custom status code
+    ///
+    /// [`send`](Self::send) always uses `200 OK`. Use this when the status code matters, like `500 Internal Server Error`.
+    pub fn send_with_status(&self, status: StatusCode) -> HttpResponse {
+        HttpResponse::build(status).json(self)
+    }
 }
@@ -13,3 +13,15 @@ pub fn run_migrations(
 
     Ok(())
 }
+
+/// Revert every migration and run them again from scratch
+///
+/// This drops all the tables, so all the data will be lost.
+pub fn rerun_migrations(
+    connection: &mut impl MigrationHarness<Sqlite>,
+) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+    connection.revert_all_migrations(MIGRATIONS)?;
+    connection.run_pending_migrations(MIGRATIONS)?;
+
+    Ok(())
+}
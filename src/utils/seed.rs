@@ -0,0 +1,68 @@
+use crate::*;
+use chrono::{Duration, Utc};
+use clap::ValueEnum;
+
+use super::generate_users;
+use super::hash::hash_password;
+
+/// Datasets that can be loaded into the database
+#[derive(Serialize, Deserialize, ValueEnum, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    /// No data at all
+    Empty,
+    /// A handful of random users
+    Small,
+    /// The user from the docs examples (`shanto` / `admin005`) and a bunch of random users
+    Demo,
+}
+
+/// Load a preset into the database
+///
+/// The existing data is kept. To start from a clean database, call [`truncate_tables`](super::truncate_tables) first.
+pub async fn seed_database(preset: Preset, conn: DbPool) -> Result<(), ()> {
+    match preset {
+        Preset::Empty => Ok(()),
+        Preset::Small => {
+            generate_users(DEFAULT_MOCK_DATA_LEN as u32, Duration::days(365), conn).await?;
+            Ok(())
+        }
+        Preset::Demo => {
+            generate_users(50, Duration::days(2 * 365), conn.clone()).await?;
+
+            let now = Utc::now().naive_utc();
+            let demo_user = UserSeed {
+                name: "Shanto Islam".to_string(),
+                username: "shanto".to_string(),
+                email: Some("shanto@gmail.com".to_string()),
+                img_url: None,
+                phone: None,
+                password: hash_password("admin005"),
+                created_at: now,
+                email_verified_at: Some(now),
+            };
+
+            let mut db_connection = conn.get().unwrap();
+
+            let result = web::block(move || {
+                diesel::insert_into(crate::schema::users::table)
+                    .values(&demo_user)
+                    .execute(&mut db_connection)
+            });
+
+            match result.await {
+                Ok(insert_result) => match insert_result {
+                    Ok(_) => Ok(()),
+                    Err(e) => {
+                        server_error(e);
+                        Err(())
+                    }
+                },
+                Err(e) => {
+                    server_error(e);
+                    Err(())
+                }
+            }
+        }
+    }
+}
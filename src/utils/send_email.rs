@@ -0,0 +1,24 @@
+use crate::schema::emails;
+use crate::*;
+
+/// "Send" an email by saving it in the local mailbox
+///
+/// Nothing leaves the machine. The emails can be read at `/__mailbox`.
+pub fn send_email(
+    recipient: &str,
+    subject: &str,
+    body: &str,
+    connection: &mut SqliteConnection,
+) -> QueryResult<()> {
+    let email = EmailNew {
+        recipient: recipient.to_string(),
+        subject: subject.to_string(),
+        body: body.to_string(),
+    };
+
+    diesel::insert_into(emails::table)
+        .values(&email)
+        .execute(connection)?;
+
+    Ok(())
+}
@@ -0,0 +1,159 @@
+use crate::*;
+use chrono::{DateTime, NaiveDateTime, Timelike, Utc};
+use rusqlite::{backup::Progress, DatabaseName};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{database_path, run_migrations};
+
+/// A saved copy of the database
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct Snapshot {
+    pub name: String,
+    /// Size of the snapshot file in bytes
+    pub size: u64,
+    pub created_at: NaiveDateTime,
+}
+
+/// The directory where the snapshots are stored
+///
+/// This is the `krapi-snapshots` directory next to the database.
+pub fn snapshots_dir() -> PathBuf {
+    let database_path = database_path();
+
+    Path::new(&database_path)
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join("krapi-snapshots")
+}
+
+/// Check if the name can be used for a snapshot
+///
+/// Only letters, digits, `-` and `_` are allowed, so the name is always a plain file name.
+pub fn is_valid_snapshot_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Check if a snapshot with the name exists
+pub fn snapshot_exists(name: &str) -> bool {
+    is_valid_snapshot_name(name) && snapshot_path(name).is_file()
+}
+
+fn snapshot_path(name: &str) -> PathBuf {
+    snapshots_dir().join(format!("{}.sqlite", name))
+}
+
+/// Save a copy of the database with the given name
+///
+/// Uses SQLite's online backup API, so it is safe to run while the server is running.
+/// An existing snapshot with the same name is overwritten.
+pub fn save_snapshot(name: &str) -> Result<Snapshot, Box<dyn Error + Send + Sync + 'static>> {
+    if !is_valid_snapshot_name(name) {
+        return Err(format!("Invalid snapshot name `{}`", name).into());
+    }
+
+    fs::create_dir_all(snapshots_dir())?;
+
+    let database = rusqlite::Connection::open(database_path())?;
+    database.backup(
+        DatabaseName::Main,
+        snapshot_path(name),
+        None::<fn(Progress)>,
+    )?;
+
+    read_snapshot(&snapshot_path(name))
+}
+
+/// Replace the database with the snapshot of the given name
+///
+/// Uses SQLite's online backup API, so it is safe to run while the server is running.
+/// The pending migrations run afterwards, so a snapshot from an older version works with this one.
+/// The current API keys are kept, like [`truncate_tables`](super::truncate_tables) does, so the clients using them aren't locked out.
+pub fn restore_snapshot(name: &str) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+    if !snapshot_exists(name) {
+        return Err(format!("Snapshot `{}` not found", name).into());
+    }
+
+    let mut database = rusqlite::Connection::open(database_path())?;
+
+    // The temp database is not touched by the restore
+    let has_api_keys = database
+        .prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'api_keys'")?
+        .exists([])?;
+    if has_api_keys {
+        database.execute_batch(
+            "DROP TABLE IF EXISTS temp.kept_api_keys; \
+             CREATE TEMP TABLE kept_api_keys AS SELECT * FROM main.api_keys;",
+        )?;
+    }
+
+    database.restore(
+        DatabaseName::Main,
+        snapshot_path(name),
+        None::<fn(Progress)>,
+    )?;
+
+    let mut connection = SqliteConnection::establish(&database_path())?;
+    run_migrations(&mut connection)?;
+
+    if has_api_keys {
+        let columns = database
+            .prepare("SELECT * FROM temp.kept_api_keys")?
+            .column_names()
+            .join(", ");
+
+        let transaction = database.transaction()?;
+        transaction.execute_batch(&format!(
+            "DELETE FROM main.api_keys; \
+             INSERT INTO main.api_keys ({columns}) SELECT {columns} FROM temp.kept_api_keys; \
+             DROP TABLE temp.kept_api_keys;",
+            columns = columns
+        ))?;
+        transaction.commit()?;
+    }
+
+    Ok(())
+}
+
+/// List all the saved snapshots, oldest first
+pub fn list_snapshots() -> Result<Vec<Snapshot>, Box<dyn Error + Send + Sync + 'static>> {
+    let dir = snapshots_dir();
+
+    // No snapshot has been saved yet
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut snapshots = Vec::new();
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        if path.extension().and_then(|ext| ext.to_str()) == Some("sqlite") {
+            snapshots.push(read_snapshot(&path)?);
+        }
+    }
+
+    snapshots.sort_by_key(|snapshot| snapshot.created_at);
+
+    Ok(snapshots)
+}
+
+fn read_snapshot(path: &Path) -> Result<Snapshot, Box<dyn Error + Send + Sync + 'static>> {
+    let metadata = fs::metadata(path)?;
+    let modified: DateTime<Utc> = metadata.modified()?.into();
+
+    Ok(Snapshot {
+        name: path
+            .file_stem()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+            .to_string(),
+        size: metadata.len(),
+        created_at: modified.naive_utc().with_nanosecond(0).unwrap(),
+    })
+}
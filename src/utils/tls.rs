@@ -0,0 +1,239 @@
+use chrono::{Datelike, Utc};
+use home::home_dir;
+use rcgen::{
+    date_time_ymd, BasicConstraints, Certificate, CertificateParams, DistinguishedName, DnType,
+    ExtendedKeyUsagePurpose, IsCa, KeyPair, KeyUsagePurpose, SanType,
+};
+use rustls::{PrivateKey, ServerConfig};
+use rustls_pemfile::Item;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::net::{IpAddr, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// How long the generated certificate is valid. Browsers don't accept much more than a year.
+const CERTIFICATE_DAYS: i64 = 397;
+
+/// A certificate and its private key, in PEM files
+pub struct TlsFiles {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+/// The directory where the local CA and certificate are cached
+///
+/// It is `~/.config/krapi-tls`, shared by every project, so the CA only has to be trusted once.
+pub fn tls_dir() -> PathBuf {
+    home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".config")
+        .join("krapi-tls")
+}
+
+/// The certificate of the local CA. Trust it in the browser or the OS to avoid the certificate warnings.
+pub fn ca_path() -> PathBuf {
+    tls_dir().join("ca.pem")
+}
+
+/// Generate a certificate for the addresses the server listens on, signed by the local CA
+///
+/// The certificate is always valid for `localhost`, `127.0.0.1` and `::1`. Listening on `0.0.0.0` or `::`
+/// adds the LAN IP, so phones on the same network can connect.
+///
+/// The CA and the certificate are cached in [`tls_dir`]. The certificate is generated again when the
+/// addresses change or it is about to expire.
+pub fn local_certificate(
+    addresses: &[String],
+) -> Result<TlsFiles, Box<dyn Error + Send + Sync + 'static>> {
+    let dir = tls_dir();
+    fs::create_dir_all(&dir)?;
+
+    let (ca, is_new_ca) = local_ca(&dir)?;
+
+    let hosts = certificate_hosts(addresses);
+    let files = TlsFiles {
+        cert: dir.join("localhost.pem"),
+        key: dir.join("localhost-key.pem"),
+    };
+    let hosts_path = dir.join("localhost.hosts");
+
+    let is_fresh = !is_new_ca
+        && fs::read_to_string(&hosts_path).ok() == Some(hosts.join("\n"))
+        && files.key.exists()
+        && fs::metadata(&files.cert)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .map(|age| age < Duration::from_secs(60 * 60 * 24 * 365))
+            .unwrap_or(false);
+
+    if is_fresh {
+        return Ok(files);
+    }
+
+    let mut params = CertificateParams::default();
+    params.distinguished_name = DistinguishedName::new();
+    params
+        .distinguished_name
+        .push(DnType::CommonName, hosts[0].as_str());
+    params.subject_alt_names = hosts
+        .iter()
+        .map(|host| match host.parse::<IpAddr>() {
+            Ok(ip) => SanType::IpAddress(ip),
+            Err(_) => SanType::DnsName(host.clone()),
+        })
+        .collect();
+    params.key_usages = vec![KeyUsagePurpose::DigitalSignature];
+    params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+    params.use_authority_key_identifier_extension = true;
+    set_validity(&mut params, CERTIFICATE_DAYS);
+
+    let certificate = Certificate::from_params(params)?;
+
+    // Send the CA along with the certificate
+    let chain = certificate.serialize_pem_with_signer(&ca)? + &fs::read_to_string(ca_path())?;
+    fs::write(&files.cert, chain)?;
+    write_private(&files.key, &certificate.serialize_private_key_pem())?;
+    fs::write(hosts_path, hosts.join("\n"))?;
+
+    Ok(files)
+}
+
+/// Load the local CA, or generate it the first time
+///
+/// Returns the CA and whether it was just generated.
+fn local_ca(dir: &Path) -> Result<(Certificate, bool), Box<dyn Error + Send + Sync + 'static>> {
+    let key_path = dir.join("ca-key.pem");
+
+    // The same name and key sign the same as the saved CA, so it doesn't have to be parsed
+    let mut params = CertificateParams::default();
+    params.distinguished_name = DistinguishedName::new();
+    params
+        .distinguished_name
+        .push(DnType::CommonName, "KR Api Local CA");
+    params
+        .distinguished_name
+        .push(DnType::OrganizationName, "KR Api");
+    params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    params.key_usages = vec![
+        KeyUsagePurpose::KeyCertSign,
+        KeyUsagePurpose::CrlSign,
+        KeyUsagePurpose::DigitalSignature,
+    ];
+    set_validity(&mut params, 365 * 10);
+
+    if key_path.exists() && ca_path().exists() {
+        params.key_pair = Some(KeyPair::from_pem(&fs::read_to_string(&key_path)?)?);
+        return Ok((Certificate::from_params(params)?, false));
+    }
+
+    let ca = Certificate::from_params(params)?;
+    fs::write(ca_path(), ca.serialize_pem()?)?;
+    write_private(&key_path, &ca.serialize_private_key_pem())?;
+
+    Ok((ca, true))
+}
+
+/// The names and IPs the certificate is valid for
+fn certificate_hosts(addresses: &[String]) -> Vec<String> {
+    let mut hosts = vec![
+        "localhost".to_string(),
+        "127.0.0.1".to_string(),
+        "::1".to_string(),
+    ];
+
+    for address in addresses {
+        // Remove the port and the brackets of IPv6
+        let host = match address.rsplit_once(':') {
+            Some((host, port)) if port.parse::<u16>().is_ok() => host,
+            _ => address.as_str(),
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+
+        let host = if host == "0.0.0.0" || host == "::" {
+            match lan_ip() {
+                Some(ip) => ip.to_string(),
+                None => continue,
+            }
+        } else {
+            host.to_string()
+        };
+
+        if !hosts.contains(&host) {
+            hosts.push(host);
+        }
+    }
+
+    hosts
+}
+
+/// The IP of this machine on the local network
+fn lan_ip() -> Option<IpAddr> {
+    // Connecting a UDP socket sends nothing, but picks the interface that would be used
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("192.0.2.1:80").ok()?;
+    let ip = socket.local_addr().ok()?.ip();
+
+    (!ip.is_unspecified()).then_some(ip)
+}
+
+/// Make the certificate valid from yesterday until `days` from today
+fn set_validity(params: &mut CertificateParams, days: i64) {
+    let day = |days: i64| {
+        let date = Utc::now().date_naive() + chrono::Duration::days(days);
+        date_time_ymd(date.year(), date.month() as u8, date.day() as u8)
+    };
+
+    params.not_before = day(-1);
+    params.not_after = day(days);
+}
+
+/// Write a private key that only the user can read
+fn write_private(path: &Path, content: &str) -> std::io::Result<()> {
+    fs::write(path, content)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    }
+
+    Ok(())
+}
+
+/// Load a certificate chain and its private key for the server
+pub fn rustls_config(
+    files: &TlsFiles,
+) -> Result<ServerConfig, Box<dyn Error + Send + Sync + 'static>> {
+    let open = |path: &Path| {
+        File::open(path)
+            .map(BufReader::new)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))
+    };
+
+    let certs: Vec<rustls::Certificate> = rustls_pemfile::certs(&mut open(&files.cert)?)?
+        .into_iter()
+        .map(rustls::Certificate)
+        .collect();
+
+    if certs.is_empty() {
+        return Err(format!("No certificate found in {}", files.cert.display()).into());
+    }
+
+    let key = rustls_pemfile::read_all(&mut open(&files.key)?)?
+        .into_iter()
+        .find_map(|item| match item {
+            Item::RSAKey(key) | Item::PKCS8Key(key) | Item::ECKey(key) => Some(PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or_else(|| format!("No private key found in {}", files.key.display()))?;
+
+    let config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)?;
+
+    Ok(config)
+}
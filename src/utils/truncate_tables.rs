@@ -0,0 +1,30 @@
+use crate::*;
+use diesel::sql_types::Text;
+
+#[derive(QueryableByName)]
+struct Table {
+    #[diesel(sql_type = Text)]
+    name: String,
+}
+
+/// Delete all the rows from every table
+///
+/// The tables themselves are kept, and the auto increment counters are reset so ids start from 1 again.
+/// The API keys are kept too, since they are credentials rather than data.
+pub fn truncate_tables(connection: &mut SqliteConnection) -> QueryResult<()> {
+    connection.transaction(|connection| {
+        // every table except sqlite's internal tables, diesel's migrations table and the API keys
+        let tables = diesel::sql_query(
+            "SELECT name FROM sqlite_master WHERE type = 'table' \
+             AND name != '__diesel_schema_migrations' AND name != 'api_keys' \
+             AND (name NOT LIKE 'sqlite_%' OR name = 'sqlite_sequence')",
+        )
+        .load::<Table>(connection)?;
+
+        for table in tables {
+            diesel::sql_query(format!("DELETE FROM \"{}\"", table.name)).execute(connection)?;
+        }
+
+        Ok(())
+    })
+}
@@ -0,0 +1,72 @@
+use qrcode::render::svg;
+use qrcode::QrCode;
+use rand::Rng;
+use std::error::Error;
+use totp_rs::{Algorithm, Secret, TOTP};
+
+use super::random_token;
+
+/// The name authenticator apps show next to the codes
+const ISSUER: &str = "KR Api";
+
+/// How many recovery codes a user gets
+const RECOVERY_CODES: usize = 10;
+
+/// Generate a new TOTP secret of 160 bits, in base32
+pub fn generate_totp_secret() -> String {
+    let secret: [u8; 20] = rand::thread_rng().gen();
+
+    // Base32 never fails to encode
+    match Secret::Raw(secret.to_vec()).to_encoded() {
+        Secret::Encoded(secret) => secret,
+        Secret::Raw(_) => unreachable!(),
+    }
+}
+
+/// The TOTP of a user, from the base32 secret
+///
+/// The codes have 6 digits and change every 30 seconds. The codes of the previous and the next
+/// 30 seconds are accepted too, for clocks that are a bit off.
+pub fn totp(secret: &str, username: &str) -> Result<TOTP, Box<dyn Error + Send + Sync + 'static>> {
+    let secret = Secret::Encoded(secret.to_string()).to_bytes()?;
+
+    // `:` separates the issuer from the account in the URI
+    let account = username.replace(':', "");
+
+    Ok(TOTP::new(
+        Algorithm::SHA1,
+        6,
+        1,
+        30,
+        secret,
+        Some(ISSUER.to_string()),
+        account,
+    )?)
+}
+
+/// Check a code from the authenticator app. Spaces in the code are ignored
+pub fn check_totp_code(totp: &TOTP, code: &str) -> bool {
+    let code = code.replace(' ', "");
+
+    totp.check_current(&code).unwrap_or(false)
+}
+
+/// Render the text as a QR code in an SVG image
+pub fn qr_svg(text: &str) -> Result<String, Box<dyn Error + Send + Sync + 'static>> {
+    let svg = QrCode::new(text.as_bytes())?
+        .render::<svg::Color>()
+        .min_dimensions(200, 200)
+        .build();
+
+    Ok(svg)
+}
+
+/// Generate new recovery codes, like `k3v9x-2mq7p`
+pub fn generate_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODES)
+        .map(|_| {
+            let code = random_token(10).to_lowercase();
+            format!("{}-{}", &code[..5], &code[5..])
+        })
+        .collect()
+}
@@ -0,0 +1,218 @@
+use crate::routes::ROUTES;
+use crate::*;
+use schemars::gen::SchemaSettings;
+use schemars::schema::{InstanceType, Schema, SchemaObject, SingleOrVec};
+use std::collections::HashSet;
+
+/// Generate TypeScript definitions for the API models
+///
+/// The definitions are derived from the same schemas as the OpenAPI description, so they always match the server.
+/// The [`Response`] envelope is generic over the type of its `data`.
+///
+/// The server sends every field of a response, with `null` when there is no value, so only the fields
+/// of request bodies are optional (`?`).
+pub fn typescript_definitions() -> String {
+    let mut gen = SchemaSettings::draft07().into_generator();
+
+    // The types that are only sent to the server
+    let mut inputs = HashSet::new();
+    let mut outputs = HashSet::from(["Response".to_string()]);
+
+    gen.subschema_for::<Response>();
+    for route in ROUTES {
+        if let Some(body_schema) = route.body_schema {
+            inputs.extend(reference_name(&body_schema(&mut gen)));
+        }
+        if let Some(data_schema) = route.data_schema {
+            outputs.extend(reference_name(&data_schema(&mut gen)));
+        }
+    }
+
+    let mut output = String::from("// Generated by `krapi typescript`. Do not edit by hand.\n");
+
+    for (name, schema) in gen.definitions() {
+        let schema = schema.clone().into_object();
+        output.push('\n');
+        output.push_str(&doc_comment(&schema, ""));
+
+        let is_object = schema
+            .object
+            .as_ref()
+            .map(|object| !object.properties.is_empty())
+            .unwrap_or(false);
+
+        if is_object {
+            let generic = if name == "Response" {
+                "<T = unknown>"
+            } else {
+                ""
+            };
+            let is_input = inputs.contains(name) && !outputs.contains(name);
+            output.push_str(&format!(
+                "export interface {}{} {}\n",
+                name,
+                generic,
+                object_type(name, &schema, "", is_input)
+            ));
+        } else {
+            output.push_str(&format!(
+                "export type {} = {};\n",
+                name,
+                schema_type(&schema, "")
+            ));
+        }
+    }
+
+    output
+}
+
+/// The TypeScript type of a schema
+fn schema_type(schema: &SchemaObject, indent: &str) -> String {
+    if let Some(reference) = &schema.reference {
+        return reference
+            .rsplit('/')
+            .next()
+            .unwrap_or(reference)
+            .to_string();
+    }
+
+    if let Some(values) = &schema.enum_values {
+        return values
+            .iter()
+            .map(|value| value.to_string())
+            .collect::<Vec<String>>()
+            .join(" | ");
+    }
+
+    if let Some(subschemas) = &schema.subschemas {
+        if let Some(schemas) = subschemas.one_of.as_ref().or(subschemas.any_of.as_ref()) {
+            return join(schemas, " | ", indent);
+        }
+        if let Some(schemas) = &subschemas.all_of {
+            return join(schemas, " & ", indent);
+        }
+    }
+
+    let types = match &schema.instance_type {
+        Some(SingleOrVec::Single(instance_type)) => vec![**instance_type],
+        Some(SingleOrVec::Vec(instance_types)) => instance_types.clone(),
+        None => return "unknown".to_string(),
+    };
+
+    types
+        .iter()
+        .map(|instance_type| match instance_type {
+            InstanceType::Null => "null".to_string(),
+            InstanceType::Boolean => "boolean".to_string(),
+            InstanceType::Number | InstanceType::Integer => "number".to_string(),
+            InstanceType::String => "string".to_string(),
+            InstanceType::Array => {
+                let item = schema
+                    .array
+                    .as_ref()
+                    .and_then(|array| array.items.as_ref())
+                    .map(|items| match items {
+                        SingleOrVec::Single(item) => to_type(item, indent),
+                        SingleOrVec::Vec(items) => format!("[{}]", join(items, ", ", indent)),
+                    })
+                    .unwrap_or_else(|| "unknown".to_string());
+
+                if item.contains(' ') {
+                    format!("({})[]", item)
+                } else {
+                    format!("{}[]", item)
+                }
+            }
+            InstanceType::Object => object_type("", schema, indent, false),
+        })
+        .collect::<Vec<String>>()
+        .join(" | ")
+}
+
+/// The body of an object type, like `{ name: string; }`
+///
+/// The fields that aren't required are optional in an input, and can be `null` otherwise.
+fn object_type(name: &str, schema: &SchemaObject, indent: &str, is_input: bool) -> String {
+    let Some(object) = &schema.object else {
+        return "Record<string, unknown>".to_string();
+    };
+
+    if object.properties.is_empty() {
+        return "Record<string, unknown>".to_string();
+    }
+
+    let inner = format!("{}  ", indent);
+    let mut output = String::from("{\n");
+
+    for (property, property_schema) in &object.properties {
+        let property_schema = property_schema.clone().into_object();
+        let is_required = object.required.contains(property);
+        let optional = if is_input && !is_required { "?" } else { "" };
+
+        // The envelope's data depends on the route
+        let mut property_type = if name == "Response" && property == "data" {
+            "T | null".to_string()
+        } else {
+            schema_type(&property_schema, &inner)
+        };
+
+        if !is_input && !is_required && !property_type.split(" | ").any(|t| t == "null") {
+            property_type.push_str(" | null");
+        }
+
+        output.push_str(&doc_comment(&property_schema, &inner));
+        output.push_str(&format!(
+            "{}{}{}: {};\n",
+            inner, property, optional, property_type
+        ));
+    }
+
+    output.push_str(indent);
+    output.push('}');
+    output
+}
+
+/// The name of the definition a schema refers to, like `UserJson`
+fn reference_name(schema: &Schema) -> Option<String> {
+    match schema {
+        Schema::Object(SchemaObject {
+            reference: Some(reference),
+            ..
+        }) => reference.rsplit('/').next().map(str::to_string),
+        _ => None,
+    }
+}
+
+fn to_type(schema: &Schema, indent: &str) -> String {
+    match schema {
+        Schema::Bool(true) => "unknown".to_string(),
+        Schema::Bool(false) => "never".to_string(),
+        Schema::Object(schema) => schema_type(schema, indent),
+    }
+}
+
+fn join(schemas: &[Schema], separator: &str, indent: &str) -> String {
+    schemas
+        .iter()
+        .map(|schema| to_type(schema, indent))
+        .collect::<Vec<String>>()
+        .join(separator)
+}
+
+/// The description of the schema as a JSDoc comment
+fn doc_comment(schema: &SchemaObject, indent: &str) -> String {
+    let Some(description) = schema
+        .metadata
+        .as_ref()
+        .and_then(|metadata| metadata.description.as_ref())
+    else {
+        return String::new();
+    };
+
+    let mut output = format!("{}/**\n", indent);
+    for line in description.lines() {
+        output.push_str(&format!("{} * {}\n", indent, line).replace(" * \n", " *\n"));
+    }
+    output.push_str(&format!("{} */\n", indent));
+    output
+}